@@ -2,7 +2,11 @@ mod linear_algebra;
 
 pub mod prelude {
     pub use crate::linear_algebra::vector::Vector;
-    pub use crate::linear_algebra::matrix::Matrix;
-    pub use crate::linear_algebra::msolver;
-    pub use crate::linear_algebra::preconditioner::{self, Preconditioner};
+    pub use crate::linear_algebra::matrix::{Matrix, MatrixError, LevelSet};
+    pub use crate::linear_algebra::triplet::TripletBuilder;
+    pub use crate::linear_algebra::symmetric::SymmetricMatrix;
+    pub use crate::linear_algebra::partition::{self, Bisection};
+    pub use crate::linear_algebra::mtx::{MtxError, Symmetry};
+    pub use crate::linear_algebra::msolver::{self, LinearOperator, LinearSolver, Solver, SolverConfig, Tolerance};
+    pub use crate::linear_algebra::preconditioner::{self, Precondition, Preconditioner};
 }
\ No newline at end of file
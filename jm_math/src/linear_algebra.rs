@@ -6,5 +6,16 @@ pub mod matrix;
 pub mod msolver;
 #[allow(non_snake_case)]
 pub mod preconditioner;
+#[allow(non_snake_case)]
+pub mod mtx;
+#[allow(non_snake_case)]
+pub mod triplet;
+#[allow(non_snake_case)]
+pub mod symmetric;
+#[allow(non_snake_case)]
+pub mod partition;
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod testing;
 // todo: shall be private module
 // mod preconditioner;
\ No newline at end of file
@@ -1,14 +1,141 @@
 use std::{
     fmt,
-    collections::HashSet,
+    error::Error,
+    cmp::Reverse,
+    collections::BTreeSet,
     convert::From,
-    ops::Mul,
+    ops::{Add, Sub, Mul, Div},
     path::PathBuf,
+    sync::OnceLock,
 };
 use rayon::prelude::*;
-use regex::Regex;
-use crate::linear_algebra::vector::Vector;
+use crate::linear_algebra::{
+    vector::Vector,
+    mtx::{self, MtxError, Symmetry},
+    partition,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatrixError {
+    RowPointerLength { expected: usize, found: usize },
+    EntryLength { aa: usize, ja: usize },
+    RowPointer { row: usize, reason: &'static str },
+    ColumnOutOfRange { row: usize, col: usize },
+    UnsortedColumns { row: usize },
+    DuplicateEntry { row: usize, col: usize },
+    MissingDiagonal { row: usize }
+}
 
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatrixError::RowPointerLength { expected, found } => {
+                write!(f, "row pointer has {found} entries, expected {expected}")
+            },
+            MatrixError::EntryLength { aa, ja } => {
+                write!(f, "{aa} values but {ja} column indices")
+            },
+            MatrixError::RowPointer { row, reason } => write!(f, "row pointer at row {row} {reason}"),
+            MatrixError::ColumnOutOfRange { row, col } => write!(f, "row {row}: column {col} out of range"),
+            MatrixError::UnsortedColumns { row } => write!(f, "row {row}: columns are not sorted"),
+            MatrixError::DuplicateEntry { row, col } => write!(f, "row {row}: duplicated entry in column {col}"),
+            MatrixError::MissingDiagonal { row } => write!(f, "row {row}: diagonal entry is missing")
+        }
+    }
+}
+
+impl Error for MatrixError {}
+
+/***********************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct LevelSet {
+    rows: Vec<usize>,
+    ptr: Vec<usize>,
+    position: Vec<usize>
+}
+
+impl LevelSet {
+    pub fn forward(M: &Matrix, UPTR: &[usize]) -> Self {
+        // levels of a forward sweep over the entries before the diagonal pointer
+        // a row depends on the rows of its lower entries
+        let m = M.num_rows();
+        let mut depth = vec![0usize; m];
+
+        for i in 0..m {
+            depth[i] = M.JA[M.IA[i]..UPTR[i]].iter()
+                .map(|&j| depth[j] + 1)
+                .max().unwrap_or(0);
+        }
+
+        LevelSet::from_depth(&depth)
+    }
+
+    pub fn backward(M: &Matrix, UPTR: &[usize]) -> Self {
+        // levels of a backward sweep over the entries after the diagonal pointer
+        let m = M.num_rows();
+        let mut depth = vec![0usize; m];
+
+        for i in (0..m).rev() {
+            depth[i] = M.JA[UPTR[i]+1..M.IA[i+1]].iter()
+                .map(|&j| depth[j] + 1)
+                .max().unwrap_or(0);
+        }
+
+        LevelSet::from_depth(&depth)
+    }
+
+    fn from_depth(depth: &[usize]) -> Self {
+        // rows bucketed by depth, ascending within a level
+        let m = depth.len();
+        let num_levels = depth.iter().max().map_or(0, |&d| d + 1);
+        let mut ptr = vec![0usize; num_levels + 1];
+
+        for &d in depth {
+            ptr[d+1] += 1;
+        }
+        for l in 0..num_levels {
+            ptr[l+1] += ptr[l];
+        }
+
+        let mut next = ptr.clone();
+        let mut rows = vec![0usize; m];
+        let mut position = vec![0usize; m];
+
+        for i in 0..m {
+            let k = next[depth[i]];
+            rows[k] = i;
+            position[i] = k;
+            next[depth[i]] += 1;
+        }
+
+        LevelSet {
+            rows,
+            ptr,
+            position
+        }
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn num_levels(&self) -> usize {
+        self.ptr.len() - 1
+    }
+
+    pub fn rows(&self) -> &Vec<usize> {
+        // rows ordered by level, level l is rows[ptr[l]..ptr[l+1]]
+        &self.rows
+    }
+
+    pub fn ptr(&self) -> &Vec<usize> {
+        &self.ptr
+    }
+
+    pub fn position(&self) -> &Vec<usize> {
+        // position of each row in rows()
+        &self.position
+    }
+}
+
+/***********************************************************************************************************/
 #[derive(Debug, Clone)]
 pub struct Matrix {
     m: usize,
@@ -16,7 +143,9 @@ pub struct Matrix {
     AA: Vec<f64>,
     JA: Vec<usize>,
     IA: Vec<usize>,
-    UPTR: Option<Vec<usize>>
+    UPTR: Option<Vec<usize>>,
+    DIA: OnceLock<Vec<usize>>,
+    LEVELS: Option<(LevelSet, LevelSet)>
 }
 
 impl Matrix {
@@ -27,7 +156,9 @@ impl Matrix {
             AA: Vec::new(), 
             JA: Vec::new(), 
             IA: Vec::new(),
-            UPTR: None
+            UPTR: None,
+            DIA: OnceLock::new(),
+            LEVELS: None
         }
     }
 
@@ -68,64 +199,258 @@ impl Matrix {
         bw
     }
 
+//-----------------------------------------------------------------------------------------------------------//
+    fn level_structure(&self, root: usize, stamp: &mut [usize], id: usize) -> Vec<Vec<usize>> {
+        // breadth first level sets from a root, limited to its connected component
+        // nodes are visited when stamp[i] != id, so stamp needs no reset between calls
+        let mut levels = vec![vec![root]];
+        stamp[root] = id;
+
+        loop {
+            let mut next = Vec::new();
+
+            for &i in levels.last().unwrap() {
+                for &j in &self.JA[self.IA[i]..self.IA[i+1]] {
+                    if stamp[j] != id {
+                        stamp[j] = id;
+                        next.push(j);
+                    }
+                }
+            }
+
+            if next.is_empty() {
+                break;
+            }
+
+            levels.push(next);
+        }
+
+        levels
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn pseudo_peripheral(&self, root: usize, degree: &[usize], stamp: &mut [usize], id: &mut usize) -> usize {
+        // George-Liu search, move to a node of minimum degree in the last level
+        // as long as the eccentricity grows
+        let mut root = root;
+        *id += 1;
+        let mut levels = self.level_structure(root, stamp, *id);
+
+        loop {
+            let candidate = *levels.last().unwrap().iter()
+                .min_by_key(|&&i| (degree[i], i))
+                .unwrap();
+
+            *id += 1;
+            let next = self.level_structure(candidate, stamp, *id);
+
+            if next.len() <= levels.len() {
+                return root;
+            }
+
+            root = candidate;
+            levels = next;
+        }
+    }
+
 //-----------------------------------------------------------------------------------------------------------//
     pub fn RCM(&self) -> Vec<usize> {
-        // reverse Cuthill-McKee
+        // reverse Cuthill-McKee on the graph of A + A^T
+        // each connected component starts from a pseudo-peripheral node
         assert!(self.num_cols() == self.num_rows());
 
+        let G = self + &self.transpose();
         let m = self.num_rows();
-        let mut next: usize;
-        let mut levset = HashSet::new();
-        let mut marker = vec![0usize; m];
-        let mut iperm = vec![usize::MAX; m];
-        let mut iter = 0;
-
-        //* find a initial node which has the minimum degree
-        let i = (0..m).into_par_iter().min_by_key(|&i| self.degree(i))
-            .expect("can not find a initial node");
-
-        levset.insert(i);
-        next = 1;
-        marker[i] = 1;
-        iperm[0] = i;
-
-        while next < m  && iter < m{
-            // println!("iter: {iter}, next: {next}, m: {m}");
-
-            let mut next_levset = HashSet::new(); 
-            let mut nodes = levset.into_iter().collect::<Vec<usize>>();
-
-            nodes.sort_by(|&i, &j| 
-                self.degree(i).cmp(&self.degree(j)).then(i.cmp(&j))
-            );
-            
-            for j in nodes {
-                //* neighbors oredered by its degree
-                let mut neighbor = self.neighbor(j);
-                neighbor.sort_by(|&i, &j|
-                    self.degree(i).cmp(&self.degree(j)).then(i.cmp(&j))
-                );
-
-                //* visiting neighbour i of a node j in degree increasing order
-                for i in neighbor {
-                    if marker[i] == 0 {
-                        next_levset.insert(i);
-                        marker[i] = 1;
-                        iperm[next] = i;
-                        next += 1;
-                    }
-                }
+        let degree = (0..m).into_par_iter().map(|i| G.degree(i)).collect::<Vec<usize>>();
+        let mut stamp = vec![usize::MAX; m];
+        let mut id = 0;
+        let mut placed = vec![false; m];
+        let mut iperm = Vec::with_capacity(m);
+
+        //* components are entered from their node of minimum degree */
+        let mut order = (0..m).collect::<Vec<usize>>();
+        order.par_sort_by_key(|&i| (degree[i], i));
+
+        for &i in &order {
+            if placed[i] {
+                continue;
             }
-            
-            levset = next_levset;
 
-            iter += 1;
+            let root = G.pseudo_peripheral(i, &degree, &mut stamp, &mut id);
+            let mut head = iperm.len();
+
+            placed[root] = true;
+            iperm.push(root);
+
+            while head < iperm.len() {
+                let j = iperm[head];
+                head += 1;
+
+                //* visiting unplaced neighbours of j in degree increasing order */
+                let mut neighbor = G.neighbor(j);
+                neighbor.retain(|&k| !placed[k]);
+                neighbor.sort_by_key(|&k| (degree[k], k));
+
+                for k in neighbor {
+                    placed[k] = true;
+                    iperm.push(k);
+                }
+            }
         }
 
         iperm.reverse();
         iperm
     }
 
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn AMD(&self) -> Vec<usize> {
+        // approximate minimum degree ordering on the graph of A + A^T
+        // quotient graph of variables and elements, an element e is the clique left
+        // by the elimination of e. degrees are the approximate external degrees of
+        // Amestoy, Davis and Duff
+        assert!(self.num_cols() == self.num_rows());
+
+        let G = self + &self.transpose();
+        let m = self.num_rows();
+
+        // A_i: variable neighbours, E_i: elements of i, L_e: variables of e
+        let mut vars = (0..m).into_par_iter()
+            .map(|i| G.neighbor(i).into_iter().filter(|&j| j != i).collect::<Vec<usize>>())
+            .collect::<Vec<_>>();
+        let mut elems = vec![Vec::<usize>::new(); m];
+        let mut members = vec![Vec::<usize>::new(); m];
+
+        let mut degree = vars.iter().map(|a| a.len()).collect::<Vec<usize>>();
+        let mut queue = (0..m).map(|i| (degree[i], i)).collect::<BTreeSet<_>>();
+        let mut absorbed = vec![false; m];
+        let mut mark = vec![usize::MAX; m];
+        let mut stamp = vec![usize::MAX; m];
+        let mut w = vec![0usize; m];
+        let mut perm = Vec::with_capacity(m);
+
+        for k in 0..m {
+            let (_, p) = queue.pop_first().unwrap();
+            perm.push(p);
+
+            //* L_p, variables reached from p directly or through its elements */
+            let mut Lp = Vec::new();
+            mark[p] = k;
+
+            for &j in &vars[p] {
+                if mark[j] != k {
+                    mark[j] = k;
+                    Lp.push(j);
+                }
+            }
+
+            for &e in &elems[p] {
+                for &j in &members[e] {
+                    if mark[j] != k {
+                        mark[j] = k;
+                        Lp.push(j);
+                    }
+                }
+
+                // elements of p are absorbed into the new element p
+                absorbed[e] = true;
+                members[e] = Vec::new();
+            }
+
+            vars[p] = Vec::new();
+            elems[p] = Vec::new();
+
+            //* prune the neighbours of p, edges inside L_p are covered by element p */
+            for &i in &Lp {
+                vars[i].retain(|&j| mark[j] != k);
+                elems[i].retain(|&e| !absorbed[e]);
+                elems[i].push(p);
+            }
+
+            //* w(e) = |L_e \ L_p| for the other elements of L_p */
+            for &i in &Lp {
+                for &e in &elems[i] {
+                    if e == p {
+                        continue;
+                    }
+
+                    if stamp[e] != k {
+                        stamp[e] = k;
+                        w[e] = members[e].len();
+                    }
+
+                    w[e] -= 1;
+                }
+            }
+
+            //* aggressive absorption, elements inside L_p add nothing to the degree */
+            for &i in &Lp {
+                for &e in &elems[i] {
+                    if e != p && w[e] == 0 && !absorbed[e] {
+                        absorbed[e] = true;
+                        members[e] = Vec::new();
+                    }
+                }
+            }
+
+            //* approximate external degrees */
+            let remaining = m - k - 1;
+
+            for &i in &Lp {
+                elems[i].retain(|&e| !absorbed[e]);
+
+                let external = elems[i].iter()
+                    .filter(|&&e| e != p)
+                    .map(|&e| w[e])
+                    .sum::<usize>();
+                let d = (vars[i].len() + Lp.len() - 1 + external)
+                    .min(degree[i] + Lp.len() - 1)
+                    .min(remaining);
+
+                queue.remove(&(degree[i], i));
+                degree[i] = d;
+                queue.insert((d, i));
+            }
+
+            members[p] = Lp;
+        }
+
+        perm
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn coloring(&self) -> Vec<usize> {
+        // greedy distance-1 coloring of the graph of A + A^T, largest degree first
+        // rows of the same color share no entry and can be relaxed in parallel
+        assert!(self.num_cols() == self.num_rows());
+
+        let G = self + &self.transpose();
+        let m = self.num_rows();
+        let mut order = (0..m).collect::<Vec<usize>>();
+        order.par_sort_by_key(|&i| (Reverse(G.degree(i)), i));
+
+        let mut color = vec![usize::MAX; m];
+        let mut forbidden = vec![usize::MAX; m + 1];
+
+        for &i in &order {
+            //* colors of the neighbours are forbidden for i */
+            for &j in &G.JA[G.IA[i]..G.IA[i+1]] {
+                if j != i && color[j] != usize::MAX {
+                    forbidden[color[j]] = i;
+                }
+            }
+
+            color[i] = (0..).find(|&c| forbidden[c] != i).unwrap();
+        }
+
+        color
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn ND(&self) -> Vec<usize> {
+        // nested dissection ordering, see partition::nested_dissection
+        partition::nested_dissection(self)
+    }
+
 //-----------------------------------------------------------------------------------------------------------//
     pub fn permutate_par(self, perm: &Vec<usize>) -> Matrix {
         // permutate matrix with permutation vector
@@ -184,6 +509,88 @@ impl Matrix {
         Matrix::from(AA, JA, IA)
     }
 
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn transpose(&self) -> Matrix {
+        // CSR to CSC conversion in O(nnz), the columns are split in blocks, one per thread.
+        // a block finds its part of each row by binary search on the sorted columns, counts the
+        // entries per column, and scatters them in row order into its own contiguous segment
+        let m = self.m;
+        let n = self.n;
+        let size = usize::max(1024, n.div_ceil(rayon::current_num_threads()));
+
+        let blocks = (0..n.div_ceil(size)).into_par_iter()
+            .map(|block| {
+                let c0 = block * size;
+                let c1 = usize::min(c0 + size, n);
+                let range = |i: usize| {
+                    let row = &self.JA[self.IA[i]..self.IA[i+1]];
+                    self.IA[i] + row.partition_point(|&j| j < c0)..self.IA[i] + row.partition_point(|&j| j < c1)
+                };
+
+                let mut IA = vec![0usize; c1 - c0 + 1];
+                for i in 0..m {
+                    for k in range(i) {
+                        IA[self.JA[k] - c0 + 1] += 1;
+                    }
+                }
+                for j in 0..c1 - c0 {
+                    IA[j+1] += IA[j];
+                }
+
+                let mut AA = vec![0f64; IA[c1 - c0]];
+                let mut JA = vec![0usize; IA[c1 - c0]];
+                let mut next = IA[..c1 - c0].to_vec();
+
+                for i in 0..m {
+                    for k in range(i) {
+                        let j = self.JA[k] - c0;
+
+                        AA[next[j]] = self.AA[k];
+                        JA[next[j]] = i;
+                        next[j] += 1;
+                    }
+                }
+
+                (IA, AA, JA)
+            }).collect::<Vec<_>>();
+
+        // the segments are concatenated, the column pointers shifted by the preceding entries
+        let mut IA = Vec::with_capacity(n + 1);
+        IA.push(0);
+        for (ptr, _, _) in &blocks {
+            let offset = IA[IA.len() - 1];
+            IA.extend(ptr[1..].iter().map(|p| p + offset));
+        }
+
+        let AA = blocks.par_iter().flat_map(|(_, AA, _)| AA.par_iter().copied()).collect::<Vec<f64>>();
+        let JA = blocks.par_iter().flat_map(|(_, _, JA)| JA.par_iter().copied()).collect::<Vec<usize>>();
+
+        Matrix::with_shape(n, m, AA, JA, IA)
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn mul_transpose(&self, x: &Vector) -> Vector {
+        // A^T x without building the transpose, each thread scatters into its own buffer
+        assert!(self.m == x.num_rows());
+
+        let n = self.n;
+
+        let AA = (0..self.m).into_par_iter()
+            .fold(|| vec![0f64; n], |mut y, i| {
+                for j in self.IA[i]..self.IA[i+1] {
+                    y[self.JA[j]] += self.AA[j] * x[i];
+                }
+
+                y
+            })
+            .reduce(|| vec![0f64; n], |mut y1, y2| {
+                y1.iter_mut().zip(y2).for_each(|(v1, v2)| *v1 += v2);
+                y1
+            });
+
+        Vector::from(AA)
+    }
+
 //-----------------------------------------------------------------------------------------------------------//
     // pub fn permutate(self, perm: &Vec<usize>) -> Matrix {
     //     // permutate matrix with permutation vector
@@ -225,94 +632,63 @@ impl Matrix {
     // }
 
 //-----------------------------------------------------------------------------------------------------------//
-    pub fn import_mtx(path: &str) -> Matrix {
-        let path = PathBuf::from(path);
-        
-        if path.exists() {
-            println!("import matrix from {}", path.display());
-            let mut m = usize::MAX; 
-            let mut n = usize::MAX; 
-            let mut z = usize::MAX;
-            let mut symmetry = false;
-            let mut re;
-            let text = std::fs::read_to_string(path)
-                .expect("can not read file");
-            let mut data = vec![];
-            
-            // header
-            re = Regex::new(r"%%MatrixMarket matrix coordinate\s(\w+)\s(\w+)").unwrap(); 
-            for cap in re.captures_iter(&text) {
-                symmetry = cap[2].to_string().eq("symmetric");
-                // println!("{}, {}", &cap[1], &cap[2]);
-            }
-
-            // parse dimension
-            re = Regex::new(r"(\d+)\s(\d+)\s(\d+)\n").unwrap(); 
-            for cap in re.captures_iter(&text) {
-                m = cap[1].trim().parse::<usize>().unwrap();
-                n = cap[2].trim().parse::<usize>().unwrap();
-                z = cap[3].trim().parse::<usize>().unwrap();
-            }
-            if m != n {
-                println!("caution: not square a matrix");
-            }
-
-            // parse each row to the tuple of (i, j, value)
-            re = Regex::new(r"(\d+)\s(\d+)\s([-|\s].+)").unwrap(); 
-            for cap in re.captures_iter(&text) {
-                data.push((
-                    cap[1].trim().parse::<usize>().unwrap(),
-                    cap[2].trim().parse::<usize>().unwrap(),
-                    cap[3].trim().parse::<f64>().unwrap(),
-                ));
-            }
+    pub fn import_mtx(path: &str) -> Result<Matrix, MtxError> {
+        // import a real, integer or pattern matrix from a Matrix Market file
+        // symmetric, skew-symmetric and array storages are expanded to general CSR
+        mtx::read_matrix(&PathBuf::from(path))
+    }
 
-            //* if symmetric matrix */
-            if symmetry {
-                let upper = data.par_iter()
-                    .filter(|(i, j, _)| i != j)
-                    .map(|(i, j, value)| {
-                        (*j, *i, *value)
-                    }).collect::<Vec<_>>();
-                data.par_extend(upper.par_iter());
-            }
+    pub fn import_mtx_complex(path: &str) -> Result<(Matrix, Matrix), MtxError> {
+        // import a matrix as (real part, imaginary part) from a Matrix Market file
+        mtx::read_complex(&PathBuf::from(path))
+    }
 
-            // sort by row
-            data.sort_by(|a, b| {
-                a.0.cmp(&b.0).then(a.1.cmp(&b.1))
-            });
+    pub fn export_mtx(&self, path: &str, symmetry: Symmetry) -> Result<(), MtxError> {
+        // export a matrix to a Matrix Market file in general or symmetric storage
+        mtx::write_matrix(self, &PathBuf::from(path), symmetry)
+    }
 
-            // assemble matrix
-            let mut AA = Vec::with_capacity(z);
-            let mut JA = Vec::with_capacity(z);
-            let mut IA = Vec::with_capacity(m);
-            let mut row = usize::MAX;
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn from<
+        T: IntoParallelIterator, 
+        U: IntoParallelIterator, 
+        V: IntoParallelIterator
+    >(AA: T, JA: U, IA: V) 
+    -> Self where T::Item: Into<f64>, U::Item: Into<usize>, V::Item: Into<usize> { 
+        let AA = AA.into_par_iter()
+            .map(|v| v.into())
+            .collect::<Vec<f64>>();
+        let JA = JA.into_par_iter()
+            .map(|v| v.into())
+            .collect::<Vec<usize>>();
+        let IA = IA.into_par_iter()
+            .map(|v| v.into())
+            .collect::<Vec<usize>>();
 
-            for (i, j, value) in &data {
-                if i - 1 != row {
-                    row = i - 1;
-                    IA.push(AA.len());
-                }
-                AA.push(*value);
-                JA.push(j - 1);
-            }
-            IA.push(data.len());
+        let m = IA.len() - 1;
+        let n = JA.par_iter().max().map_or(0, |&j| j + 1);
 
-            println!("done.");
-            Matrix::from(AA, JA, IA)
-        } else {            
-            println!("can not find file {}", path.display());
-            Matrix::new()
+        Matrix {
+            m,
+            n,
+            AA,
+            JA,
+            IA,
+            UPTR: None,
+            DIA: OnceLock::new(),
+            LEVELS: None
         }
     }
 
 //-----------------------------------------------------------------------------------------------------------//
-    pub fn from<
+    pub fn from_shape<
         T: IntoParallelIterator, 
         U: IntoParallelIterator, 
         V: IntoParallelIterator
-    >(AA: T, JA: U, IA: V) 
+    >(m: usize, n: usize, AA: T, JA: U, IA: V) 
     -> Self where T::Item: Into<f64>, U::Item: Into<usize>, V::Item: Into<usize> { 
+        // CSR arrays of a [m x n] matrix, empty trailing rows and columns are kept
+        // the arrays are not checked, see validate()
         let AA = AA.into_par_iter()
             .map(|v| v.into())
             .collect::<Vec<f64>>();
@@ -323,19 +699,215 @@ impl Matrix {
             .map(|v| v.into())
             .collect::<Vec<usize>>();
 
-        let m = IA.len() - 1;
-        let n = *JA.par_iter().max().unwrap() + 1;
+        Matrix::with_shape(m, n, AA, JA, IA)
+    }
 
+//-----------------------------------------------------------------------------------------------------------//
+    pub(crate) fn with_shape(m: usize, n: usize, AA: Vec<f64>, JA: Vec<usize>, IA: Vec<usize>) -> Self {
+        // CSR arrays of a [m x n] matrix, no inference of the dimension
         Matrix {
             m,
             n,
             AA,
             JA,
             IA,
-            UPTR: None
+            UPTR: None,
+            DIA: OnceLock::new(),
+            LEVELS: None
         }
     }
 
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn from_dense<I: IntoIterator>(rows: I, keep_zeros: bool) -> Self
+    where I::Item: IntoIterator, <<I as IntoIterator>::Item as IntoIterator>::Item: Into<f64> {
+        // CSR matrix from dense rows, zeros are dropped unless keep_zeros is set
+        let rows = rows.into_iter()
+            .map(|row| row.into_iter().map(|v| v.into()).collect::<Vec<f64>>())
+            .collect::<Vec<_>>();
+
+        let m = rows.len();
+        let n = rows.first().map_or(0, |row| row.len());
+
+        if rows.iter().any(|row| row.len() != n) {
+            panic!("all rows of a dense matrix must have the same length");
+        }
+
+        let mut AA = Vec::new();
+        let mut JA = Vec::new();
+        let mut IA = Vec::with_capacity(m + 1);
+
+        IA.push(0);
+        for row in rows {
+            for (j, v) in row.into_iter().enumerate() {
+                if keep_zeros || v != 0f64 {
+                    AA.push(v);
+                    JA.push(j);
+                }
+            }
+            IA.push(AA.len());
+        }
+
+        Matrix::with_shape(m, n, AA, JA, IA)
+    }
+
+    pub fn to_dense(&self) -> Vec<Vec<f64>> {
+        // dense row-major representation
+        (0..self.m).into_par_iter()
+            .map(|i| {
+                let mut row = vec![0f64; self.n];
+                for j in self.IA[i]..self.IA[i+1] {
+                    row[self.JA[j]] += self.AA[j];
+                }
+
+                row
+            }).collect()
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn validate(&self) -> Result<(), MatrixError> {
+        // check the CSR invariants, sorted columns without duplicates and a present diagonal
+        // which GS, SGS, ILU, IC and LU_solve rely on
+        let m = self.m;
+        let n = self.n;
+        let nnz = self.JA.len();
+
+        if self.IA.len() != m + 1 {
+            return Err(MatrixError::RowPointerLength { expected: m + 1, found: self.IA.len() });
+        }
+
+        if self.AA.len() != nnz {
+            return Err(MatrixError::EntryLength { aa: self.AA.len(), ja: nnz });
+        }
+
+        if self.IA[0] != 0 {
+            return Err(MatrixError::RowPointer { row: 0, reason: "does not start at zero" });
+        }
+
+        if let Some(i) = (0..m).find(|&i| self.IA[i] > self.IA[i+1]) {
+            return Err(MatrixError::RowPointer { row: i, reason: "is decreasing" });
+        }
+
+        if self.IA[m] != nnz {
+            return Err(MatrixError::RowPointer { row: m, reason: "does not end at the number of entries" });
+        }
+
+        let error = (0..m).into_par_iter()
+            .map(|i| {
+                let cols = &self.JA[self.IA[i]..self.IA[i+1]];
+
+                for (k, &j) in cols.iter().enumerate() {
+                    if j >= n {
+                        return Err(MatrixError::ColumnOutOfRange { row: i, col: j });
+                    }
+
+                    if k > 0 && j == cols[k-1] {
+                        return Err(MatrixError::DuplicateEntry { row: i, col: j });
+                    }
+
+                    if k > 0 && j < cols[k-1] {
+                        return Err(MatrixError::UnsortedColumns { row: i });
+                    }
+                }
+
+                if i < n && cols.binary_search(&i).is_err() {
+                    return Err(MatrixError::MissingDiagonal { row: i });
+                }
+
+                Ok(())
+            }).find_first(|result| result.is_err());
+
+        error.unwrap_or(Ok(()))
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub(crate) fn from_rows(n: usize, rows: Vec<Vec<(usize, f64)>>) -> Self {
+        // CSR matrix from rows of sorted (column, value) pairs
+        let m = rows.len();
+
+        let AA = rows.par_iter()
+            .map(|row| row.iter().map(|&(_, v)| v).collect::<Vec<_>>())
+            .flatten().collect::<Vec<_>>();
+
+        let JA = rows.par_iter()
+            .map(|row| row.iter().map(|&(j, _)| j).collect::<Vec<_>>())
+            .flatten().collect::<Vec<_>>();
+
+        let mut IA = Vec::with_capacity(m + 1);
+        IA.push(0);
+        for i in 0..m {
+            IA.push(IA[i] + rows[i].len())
+        }
+
+        Matrix::with_shape(m, n, AA, JA, IA)
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn merge(&self, rhs: &Matrix, a: f64) -> Matrix {
+        // self + a * rhs on the union of both sparsity patterns
+        assert!(self.m == rhs.m && self.n == rhs.n);
+
+        let rows = (0..self.m).into_par_iter()
+            .map(|i| {
+                let (mut k1, k2) = (self.IA[i], self.IA[i+1]);
+                let (mut l1, l2) = (rhs.IA[i], rhs.IA[i+1]);
+                let mut row = Vec::with_capacity(k2 - k1 + l2 - l1);
+
+                while k1 < k2 || l1 < l2 {
+                    let j1 = if k1 < k2 { self.JA[k1] } else { usize::MAX };
+                    let j2 = if l1 < l2 { rhs.JA[l1] } else { usize::MAX };
+
+                    if j1 < j2 {
+                        row.push((j1, self.AA[k1]));
+                        k1 += 1;
+                    } else if j2 < j1 {
+                        row.push((j2, a * rhs.AA[l1]));
+                        l1 += 1;
+                    } else {
+                        row.push((j1, self.AA[k1] + a * rhs.AA[l1]));
+                        k1 += 1;
+                        l1 += 1;
+                    }
+                }
+
+                row
+            }).collect::<Vec<_>>();
+
+        Matrix::from_rows(self.n, rows)
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn scale(&self, a: f64) -> Matrix {
+        // a * self with the same sparsity pattern
+        let AA = self.AA.par_iter()
+            .map(|v| a * v)
+            .collect::<Vec<f64>>();
+
+        Matrix::with_shape(self.m, self.n, AA, self.JA.clone(), self.IA.clone())
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn is_symmetric(&self, tol: f64) -> bool {
+        // |a(i,j) - a(j,i)| <= tol * max(|a(i,j)|, |a(j,i)|) for all entries, missing entries are zero
+        // columns are assumed to be sorted
+        if self.m != self.n {
+            return false;
+        }
+
+        (0..self.m).into_par_iter()
+            .all(|i| {
+                (self.IA[i]..self.IA[i+1]).all(|k| {
+                    let j = self.JA[k];
+                    let a = self.AA[k];
+                    let b = match self.JA[self.IA[j]..self.IA[j+1]].binary_search(&i) {
+                        Ok(l) => self.AA[self.IA[j] + l],
+                        Err(_) => 0f64
+                    };
+
+                    (a - b).abs() <= tol * f64::max(a.abs(), b.abs())
+                })
+            })
+    }
+
 //-----------------------------------------------------------------------------------------------------------//
     pub fn num_rows(&self) -> usize {
         self.m
@@ -363,6 +935,108 @@ impl Matrix {
 
     pub fn set_dia_ptr(&mut self, UPTR: Vec<usize>) {
         self.UPTR = Some(UPTR);
+        self.LEVELS = None;
+    }
+
+    pub fn set_levels(&mut self) {
+        // cache the forward and backward level sets of an LU factor for LU_solve
+        let UPTR = match self.UPTR.as_ref() {
+            Some(UPTR) => UPTR,
+            None => panic!("can not find diagonal pointer")
+        };
+
+        self.LEVELS = Some((LevelSet::forward(self, UPTR), LevelSet::backward(self, UPTR)));
+    }
+
+    pub fn levels(&self) -> Option<(&LevelSet, &LevelSet)> {
+        self.LEVELS.as_ref().map(|(forward, backward)| (forward, backward))
+    }
+
+    pub fn dia_ptr(&self) -> &Vec<usize> {
+        // position of the diagonal entry in each row, usize::MAX if the row has none
+        // computed on first use and cached on the matrix, apart from the UPTR of a factor
+        self.DIA.get_or_init(|| {
+            (0..self.m).into_par_iter()
+                .map(|i| {
+                    let j1 = self.IA[i];
+                    let j2 = self.IA[i+1];
+
+                    match self.JA[j1..j2].binary_search(&i) {
+                        Ok(k) => j1 + k,
+                        Err(_) => usize::MAX
+                    }
+                }).collect()
+        })
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn diagonal(&self) -> Vector {
+        // diagonal entries, zero where the diagonal is not stored
+        let UPTR = self.dia_ptr();
+
+        let AA = UPTR.par_iter()
+            .map(|&j| if j == usize::MAX { 0f64 } else { self.AA[j] })
+            .collect::<Vec<f64>>();
+
+        Vector::from(AA)
+    }
+
+    pub fn row_sums(&self) -> Vector {
+        // sum of the entries in each row, A * [1, 1, ..., 1]
+        let AA = (0..self.m).into_par_iter()
+            .map(|i| self.AA[self.IA[i]..self.IA[i+1]].iter().sum())
+            .collect::<Vec<f64>>();
+
+        Vector::from(AA)
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn norm_1(&self) -> f64 {
+        // maximum absolute column sum
+        let n = self.n;
+
+        (0..self.m).into_par_iter()
+            .fold(|| vec![0f64; n], |mut sums, i| {
+                for j in self.IA[i]..self.IA[i+1] {
+                    sums[self.JA[j]] += self.AA[j].abs();
+                }
+
+                sums
+            })
+            .reduce(|| vec![0f64; n], |mut s1, s2| {
+                s1.iter_mut().zip(s2).for_each(|(v1, v2)| *v1 += v2);
+                s1
+            })
+            .into_iter().fold(0f64, f64::max)
+    }
+
+    pub fn norm_inf(&self) -> f64 {
+        // maximum absolute row sum
+        (0..self.m).into_par_iter()
+            .map(|i| self.AA[self.IA[i]..self.IA[i+1]].iter().map(|v| v.abs()).sum::<f64>())
+            .reduce(|| 0f64, f64::max)
+    }
+
+    pub fn norm_frobenius(&self) -> f64 {
+        self.AA.par_iter()
+            .map(|v| v * v)
+            .sum::<f64>().sqrt()
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn is_diagonally_dominant(&self, strict: bool) -> bool {
+        // |a(i,i)| >= sum of |a(i,j)|, j != i, in every row, > if strict
+        let UPTR = self.dia_ptr();
+
+        (0..self.m).into_par_iter()
+            .all(|i| {
+                let diag = if UPTR[i] == usize::MAX { 0f64 } else { self.AA[UPTR[i]].abs() };
+                let off = self.AA[self.IA[i]..self.IA[i+1]].iter()
+                    .map(|v| v.abs())
+                    .sum::<f64>() - diag;
+
+                if strict { diag > off } else { diag >= off }
+            })
     }
 }
 
@@ -370,7 +1044,7 @@ impl Mul<&Vector> for &Matrix {
     type Output = Vector;
 
     fn mul(self, rhs: &Vector) -> Self::Output {
-        assert!(self.m == rhs.num_rows());
+        assert!(self.n == rhs.num_rows());
         
         let m = self.m;
         let mut AA = vec![0f64; m];
@@ -386,19 +1060,80 @@ impl Mul<&Vector> for &Matrix {
     }
 }
 
+impl Mul<&Matrix> for &Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: &Matrix) -> Self::Output {
+        // Gustavson's row-by-row product, a dense accumulator per thread
+        assert!(self.n == rhs.m);
+
+        let n = rhs.n;
+
+        let rows = (0..self.m).into_par_iter()
+            .map_init(|| (vec![usize::MAX; n], vec![0f64; n]), |(marker, acc), i| {
+                let mut cols = Vec::new();
+
+                for k in self.IA[i]..self.IA[i+1] {
+                    let a = self.AA[k];
+                    let krow = self.JA[k];
+
+                    for l in rhs.IA[krow]..rhs.IA[krow+1] {
+                        let j = rhs.JA[l];
+
+                        if marker[j] != i {
+                            marker[j] = i;
+                            acc[j] = 0f64;
+                            cols.push(j);
+                        }
+
+                        acc[j] += a * rhs.AA[l];
+                    }
+                }
+
+                cols.sort_unstable();
+                cols.into_iter().map(|j| (j, acc[j])).collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+
+        Matrix::from_rows(n, rows)
+    }
+}
+
+impl Add<&Matrix> for &Matrix {
+    type Output = Matrix;
+
+    fn add(self, rhs: &Matrix) -> Self::Output {
+        self.merge(rhs, 1f64)
+    }
+}
+
+impl Sub<&Matrix> for &Matrix {
+    type Output = Matrix;
+
+    fn sub(self, rhs: &Matrix) -> Self::Output {
+        self.merge(rhs, -1f64)
+    }
+}
+
+impl Mul<&Matrix> for f64 {
+    type Output = Matrix;
+
+    fn mul(self, rhs: &Matrix) -> Self::Output {
+        rhs.scale(self)
+    }
+}
+
+impl Div<f64> for &Matrix {
+    type Output = Matrix;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        self.scale(1f64 / rhs)
+    }
+}
+
 impl<I: IntoIterator> From<I> for Matrix 
     where I::Item: IntoIterator, <<I as IntoIterator>::Item as IntoIterator>::Item: Into<f64> {
     fn from(value: I) -> Self {
-
-        for v in value {
-            for v2 in v {
-                let tmp: f64 = v2.into();
-                print!("{} ", tmp);
-            }
-            println!();
-        }
-
-        Matrix::new()
+        Matrix::from_dense(value, false)
     }
 }
 
@@ -430,10 +1165,197 @@ impl fmt::Display for Matrix {
     } 
 }
 
-// #[cfg(test)]
-// mod tests {
-//     #[test]
-//     fn RCM_test() {
-//         assert!(false);
-//     }
-// }
\ No newline at end of file
+/***********************************************************************************************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linear_algebra::{triplet::TripletBuilder, testing::{convection_diffusion, scrambled_laplacians}};
+
+    fn is_permutation(perm: &[usize], m: usize) -> bool {
+        let mut sorted = perm.to_vec();
+        sorted.sort_unstable();
+        sorted == (0..m).collect::<Vec<usize>>()
+    }
+
+    fn rectangular() -> Matrix {
+        Matrix::from_dense([[1.0, 0.0, -2.0, 0.0], [0.0, 3.0, 0.0, 4.0], [-5.0, 0.0, 6.0, 0.0]], false)
+    }
+
+    fn dense_transpose(D: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        (0..D[0].len()).map(|j| D.iter().map(|row| row[j]).collect()).collect()
+    }
+
+    fn dense_product(D: &[Vec<f64>], E: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        D.iter().map(|row| (0..E[0].len()).map(|j| row.iter().zip(E).map(|(d, e)| d * e[j]).sum()).collect()).collect()
+    }
+
+    fn cholesky_nnz(A: &Matrix) -> usize {
+        // nonzeros of the Cholesky factor of a symmetric pattern, by symbolic elimination
+        let m = A.num_rows();
+        let mut rows = (0..m)
+            .map(|i| A.neighbor(i).into_iter().filter(|&j| j > i).collect::<BTreeSet<usize>>())
+            .collect::<Vec<_>>();
+        let mut nnz = m;
+
+        for k in 0..m {
+            let row = std::mem::take(&mut rows[k]);
+            nnz += row.len();
+
+            if let Some(&parent) = row.first() {
+                rows[parent].extend(row.iter().skip(1));
+            }
+        }
+
+        nnz
+    }
+
+    #[test]
+    fn rcm_is_a_permutation_reducing_bandwidth() {
+        let A = scrambled_laplacians(20, 15, 2);
+        let perm = A.RCM();
+
+        assert!(is_permutation(&perm, A.num_rows()));
+
+        let before = A.bandwidth();
+        let after = A.permutate_par(&perm).bandwidth();
+
+        assert!(after <= 20, "bandwidth {before} -> {after}");
+    }
+
+    #[test]
+    fn amd_is_a_permutation_reducing_fill() {
+        let A = scrambled_laplacians(30, 30, 2);
+        let perm = A.AMD();
+
+        assert!(is_permutation(&perm, A.num_rows()));
+
+        let rcm = cholesky_nnz(&A.clone().permutate_par(&A.RCM()));
+        let amd = cholesky_nnz(&A.permutate_par(&perm));
+
+        assert!(amd < rcm, "fill of AMD {amd}, RCM {rcm}");
+    }
+
+    #[test]
+    fn coloring_is_valid_on_unsymmetric_pattern() {
+        // a one-sided coupling shall still separate the colors
+        let mut builder = TripletBuilder::new(4, 4);
+        builder.extend([(0, 0, 1.0), (1, 1, 1.0), (2, 2, 1.0), (3, 3, 1.0), (0, 3, 1.0), (2, 1, 1.0)]);
+
+        for A in [scrambled_laplacians(12, 9, 2), builder.build()] {
+            let color = A.coloring();
+
+            for i in 0..A.num_rows() {
+                for &j in &A.neighbor(i) {
+                    assert!(i == j || color[i] != color[j], "({i}, {j}) share color {}", color[i]);
+                }
+            }
+
+            assert!(color.iter().all(|&c| c < 5));
+        }
+    }
+
+    #[test]
+    fn validate_reports_each_error() {
+        let check = |AA: Vec<f64>, JA: Vec<usize>, IA: Vec<usize>, error: MatrixError| {
+            assert_eq!(Matrix::from_shape(2, 2, AA, JA, IA).validate(), Err(error));
+        };
+
+        check(vec![1.0, 1.0], vec![0, 1], vec![0, 1], MatrixError::RowPointerLength { expected: 3, found: 2 });
+        check(vec![1.0], vec![0, 1], vec![0, 1, 2], MatrixError::EntryLength { aa: 1, ja: 2 });
+        check(vec![1.0, 1.0], vec![0, 1], vec![1, 1, 2], MatrixError::RowPointer { row: 0, reason: "does not start at zero" });
+        check(vec![1.0, 1.0], vec![0, 1], vec![0, 2, 1], MatrixError::RowPointer { row: 1, reason: "is decreasing" });
+        check(vec![1.0, 1.0], vec![0, 1], vec![0, 1, 1], MatrixError::RowPointer { row: 2, reason: "does not end at the number of entries" });
+        check(vec![1.0, 1.0, 1.0], vec![0, 1, 2], vec![0, 1, 3], MatrixError::ColumnOutOfRange { row: 1, col: 2 });
+        check(vec![1.0, 1.0, 1.0], vec![0, 1, 0], vec![0, 1, 3], MatrixError::UnsortedColumns { row: 1 });
+        check(vec![1.0, 1.0, 1.0], vec![0, 1, 1], vec![0, 1, 3], MatrixError::DuplicateEntry { row: 1, col: 1 });
+        check(vec![1.0, 1.0], vec![0, 0], vec![0, 1, 2], MatrixError::MissingDiagonal { row: 1 });
+    }
+
+    #[test]
+    fn from_shape_keeps_empty_rows_and_columns() {
+        // [2 x 0 1 x], with an empty last row and an empty last column
+        let A = Matrix::from_shape(3, 4, vec![2.0, 1.0], vec![0usize, 2], vec![0usize, 1, 2, 2]);
+
+        assert_eq!((A.num_rows(), A.num_cols()), (3, 4));
+        assert_eq!(A.to_dense(), vec![vec![2.0, 0.0, 0.0, 0.0], vec![0.0, 0.0, 1.0, 0.0], vec![0.0; 4]]);
+        assert_eq!(A.validate(), Err(MatrixError::MissingDiagonal { row: 1 }));
+
+        let B = Matrix::from(vec![2.0, 1.0], vec![0usize, 2], vec![0usize, 1, 2, 2]);
+        assert_eq!((B.num_rows(), B.num_cols()), (3, 3));
+    }
+
+    #[test]
+    fn transpose_of_a_rectangular_matrix() {
+        let A = rectangular();
+        let T = A.transpose();
+        let x = Vector::from(vec![1.0, -2.0, 3.0]);
+
+        assert_eq!((T.num_rows(), T.num_cols()), (4, 3));
+        assert_eq!(T.to_dense(), dense_transpose(&A.to_dense()));
+        assert_eq!(A.mul_transpose(&x), &T * &x);
+    }
+
+    #[test]
+    fn transpose_in_column_blocks() {
+        // four threads split the 3600 columns in blocks, the matrix is nonsymmetric
+        let A = convection_diffusion(60, 2.0);
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let T = pool.install(|| A.transpose());
+        let S = A.transpose();
+        let x = Vector::from((0..A.num_rows()).map(|i| (i % 5) as f64).collect::<Vec<f64>>());
+
+        assert_eq!((T.AA(), T.JA(), T.IA()), (S.AA(), S.JA(), S.IA()));
+        assert_eq!((T.transpose().AA(), T.transpose().JA()), (A.AA(), A.JA()));
+        assert!((&A.mul_transpose(&x) - &(&T * &x)).l2_norm() < 1.0E-12 * (&T * &x).l2_norm());
+    }
+
+    #[test]
+    fn arithmetic_against_dense_results() {
+        let A = rectangular();
+        let B = Matrix::from_dense([[0.0, 1.0, 2.0, 0.0], [0.0, -3.0, 0.0, 0.0], [5.0, 0.0, 0.0, 7.0]], false);
+        let (D, E) = (A.to_dense(), B.to_dense());
+        let combine = |f: fn(f64, f64) -> f64| -> Vec<Vec<f64>> {
+            D.iter().zip(&E).map(|(d, e)| d.iter().zip(e).map(|(&a, &b)| f(a, b)).collect()).collect()
+        };
+
+        // A - A keeps the pattern of A with explicit zeros
+        let Z = &A - &A;
+        assert_eq!(Z.JA(), A.JA());
+        assert!(Z.AA().iter().all(|&v| v == 0f64));
+
+        assert_eq!((&A + &B).to_dense(), combine(|a, b| a + b));
+        assert_eq!((&A - &B).to_dense(), combine(|a, b| a - b));
+        assert_eq!((2.5 * &A).to_dense(), combine(|a, _| 2.5 * a));
+        assert_eq!((&A / 2.0).to_dense(), combine(|a, _| a / 2.0));
+
+        let P = &A * &A.transpose();
+        assert_eq!((P.num_rows(), P.num_cols()), (3, 3));
+        assert_eq!(P.to_dense(), dense_product(&D, &dense_transpose(&D)));
+        assert_eq!((&A.transpose() * &B).to_dense(), dense_product(&dense_transpose(&D), &E));
+    }
+
+    #[test]
+    fn diagonal_sums_and_norms_of_a_known_matrix() {
+        let A = rectangular();
+
+        assert_eq!(A.diagonal(), Vector::from(vec![1.0, 3.0, 6.0]));
+        assert_eq!(A.row_sums(), Vector::from(vec![-1.0, 7.0, 1.0]));
+        assert_eq!(A.norm_1(), 8.0);
+        assert_eq!(A.norm_inf(), 11.0);
+        assert_eq!(A.norm_frobenius(), 91f64.sqrt());
+
+        // the diagonal is zero where it is not stored
+        let B = Matrix::from_dense([[0.0, 2.0], [1.0, 3.0]], false);
+        assert_eq!(B.diagonal(), Vector::from(vec![0.0, 3.0]));
+    }
+
+    #[test]
+    fn diagonal_dominance() {
+        let A = scrambled_laplacians(6, 5, 2);
+        assert!(A.is_diagonally_dominant(false) && !A.is_diagonally_dominant(true));
+
+        let B = Matrix::from_dense([[3.0, -1.0], [2.0, -3.0]], false);
+        assert!(B.is_diagonally_dominant(true));
+        assert!(!rectangular().is_diagonally_dominant(false));
+    }
+}
@@ -1,20 +1,189 @@
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use rayon::prelude::*;
 use crate::linear_algebra::{
     vector::Vector,
     matrix::Matrix,
-    preconditioner as precon,
-    preconditioner::Preconditioner
+    symmetric::SymmetricMatrix,
+    preconditioner::{Precondition, Preconditioner}
 };
 
-pub fn GMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, preconditioner: Preconditioner) -> Vector {
+//-----------------------------------------------------------------------------------------------------------//
+pub trait LinearSolver {
+    fn solve(&self, A: &Matrix, b: &Vector, config: &SolverConfig) -> SolveResult;
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub trait LinearOperator {
+    // a matrix storage CG and PCG can run on
+    fn num_rows(&self) -> usize;
+    fn num_cols(&self) -> usize;
+    fn spmv(&self, x: &Vector) -> Vector;
+    fn precondition(&self, preconditioner: &Preconditioner) -> Option<Arc<dyn Precondition>>;
+}
+
+impl LinearOperator for Matrix {
+    fn num_rows(&self) -> usize {
+        Matrix::num_rows(self)
+    }
+
+    fn num_cols(&self) -> usize {
+        Matrix::num_cols(self)
+    }
+
+    fn spmv(&self, x: &Vector) -> Vector {
+        self * x
+    }
+
+    fn precondition(&self, preconditioner: &Preconditioner) -> Option<Arc<dyn Precondition>> {
+        preconditioner.from(self)
+    }
+}
+
+impl LinearOperator for SymmetricMatrix {
+    fn num_rows(&self) -> usize {
+        SymmetricMatrix::num_rows(self)
+    }
+
+    fn num_cols(&self) -> usize {
+        SymmetricMatrix::num_cols(self)
+    }
+
+    fn spmv(&self, x: &Vector) -> Vector {
+        self * x
+    }
+
+    fn precondition(&self, preconditioner: &Preconditioner) -> Option<Arc<dyn Precondition>> {
+        preconditioner.from_symmetric(self)
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Solver {
+    GMRES,
+    HGMRES,
+    BiCGSTAB,
+    CG,
+    PCG,
+    GaussSeidel,
+    SOR(f64),
+    SSOR(f64),
+    MulticolorSOR(f64)
+}
+
+impl LinearSolver for Solver {
+    fn solve(&self, A: &Matrix, b: &Vector, config: &SolverConfig) -> SolveResult {
+        // CG, Gauss-Seidel and SOR do not use the restart and the preconditioner
+        let iMax = config.max_iter;
+        let tol = config.tolerance.relative(b);
+        let restart = config.restart;
+        let preconditioner = config.preconditioner.clone();
+        let x0 = config.initial_guess.as_ref();
+
+        match self {
+            Solver::GMRES => GMRES(iMax, tol, restart, A, b, preconditioner, x0),
+            Solver::HGMRES => HGMRES(iMax, tol, restart, A, b, preconditioner, x0),
+            Solver::BiCGSTAB => BiCGSTAB(iMax, tol, A, b, preconditioner, x0),
+            Solver::CG => CG(iMax, tol, A, b, x0),
+            Solver::PCG => PCG(iMax, tol, A, b, preconditioner, x0),
+            Solver::GaussSeidel => Gauss_Seidel(iMax, tol, A, b, x0),
+            Solver::SOR(omega) => SOR(iMax, tol, *omega, A, b, x0),
+            Solver::SSOR(omega) => SSOR(iMax, tol, *omega, A, b, x0),
+            Solver::MulticolorSOR(omega) => Multicolor_SOR(iMax, tol, *omega, A, b, x0)
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+    Relative(f64),
+    Absolute(f64)
+}
+
+impl Tolerance {
+    pub fn relative(&self, b: &Vector) -> f64 {
+        // solvers stop on the relative residual |r| / |b|
+        match self {
+            Tolerance::Relative(tol) => *tol,
+            Tolerance::Absolute(tol) => tol / b.l2_norm()
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    pub max_iter: usize,
+    pub tolerance: Tolerance,
+    pub restart: usize,
+    pub preconditioner: Preconditioner,
+    pub initial_guess: Option<Vector>
+}
+
+impl SolverConfig {
+    pub fn new() -> Self {
+        SolverConfig {
+            max_iter: 1000,
+            tolerance: Tolerance::Relative(1.0E-6),
+            restart: 10,
+            preconditioner: Preconditioner::None,
+            initial_guess: None
+        }
+    }
+
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    pub fn tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn restart(mut self, restart: usize) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    pub fn preconditioner(mut self, preconditioner: Preconditioner) -> Self {
+        self.preconditioner = preconditioner;
+        self
+    }
+
+    pub fn initial_guess(mut self, x0: Vector) -> Self {
+        self.initial_guess = Some(x0);
+        self
+    }
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig::new()
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn GMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, preconditioner: Preconditioner, x0: Option<&Vector>) -> SolveResult {
     assert!(A.num_cols() == b.num_rows());
 
     let m = b.num_rows();
     let bl = b.l2_norm();
+
+    if bl == 0f64 {
+        return zero_rhs(label("GMRES", Some(restart), &preconditioner), m);
+    }
     let mut iter = 0;
-    let mut residual = f64::MAX;
-    let mut x = Vector::from(vec![0.0; m]);
+    let mut x = initial_guess(m, x0);
+    let mut residual = (b - &(A * &x)).l2_norm() / bl;
     let P = preconditioner.from(A);
+    let mut history = Vec::new();
+    let time = Instant::now();
 
     while iter < iMax && residual > tol {
         assert!(A.num_cols() == b.num_rows());
@@ -33,7 +202,7 @@ pub fn GMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, prec
 
             //* right preconditioning w = A * M_inv * w
             let mut w = match &P {
-                Some(M) => A * &precon::LU_solve(M, &V[j]),
+                Some(M) => A * &M.apply(&V[j]),
                 None => A * &V[j]
             };
             
@@ -46,7 +215,7 @@ pub fn GMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, prec
             H.push(h);
 
             if H[j][j+1].abs() < tol {
-                println!("lucky breakdown");
+                // lucky breakdown
                 break;
             }
 
@@ -67,38 +236,45 @@ pub fn GMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, prec
         
         //* right preconditioning x = x + M_inv * z
         x = match &P {
-            Some(M) => &x + &precon::LU_solve(M, &z),
+            Some(M) => &x + &M.apply(&z),
             None => &x + &z
         };
 
         iter += 1;
         residual = g[H.len()].abs() / bl;
+        history.push(residual);
     }
 
-    let log = Log {
-        solver: "GMRES",
-        precon: preconditioner,
-        restart: Some(restart),
-        iMax,
-        iter,
-        residual
-    };
-
-    display(log);
-    
-    x
+    let reason = stop_reason(residual, tol, None);
+
+    SolveResult {
+        solver: label("GMRES", Some(restart), &preconditioner),
+        solution: x,
+        iterations: iter,
+        residual,
+        converged: reason == StopReason::Converged,
+        history,
+        elapsed: time.elapsed(),
+        reason
+    }
 }
 
 //-----------------------------------------------------------------------------------------------------------//
-pub fn HGMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, preconditioner: Preconditioner) -> Vector {
+pub fn HGMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, preconditioner: Preconditioner, x0: Option<&Vector>) -> SolveResult {
     assert!(A.num_cols() == b.num_rows());
 
     let m = b.num_rows();
     let bl = b.l2_norm();
+
+    if bl == 0f64 {
+        return zero_rhs(label("HGMRES", Some(restart), &preconditioner), m);
+    }
     let mut iter = 0;
-    let mut residual = f64::MAX;
-    let mut x = Vector::from(vec![0.0; m]); 
+    let mut x = initial_guess(m, x0);
+    let mut residual = (b - &(A * &x)).l2_norm() / bl;
     let P = preconditioner.from(A);
+    let mut history = Vec::new();
+    let time = Instant::now();
 
     while iter < iMax && residual > tol {
         // let mut degree = 0;
@@ -138,7 +314,7 @@ pub fn HGMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, pre
                 H.push(h);
 
                 if H[j-1][j].abs() < tol {
-                    println!("lucky breakdown");
+                    // lucky breakdown
                     break;
                 }
             } else {
@@ -164,7 +340,7 @@ pub fn HGMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, pre
             //* calculate z = P(j) .. P(1) P(0) A v(j)
             //* right preconditioning z = A * M_inv * v(j)
             z = match &P {
-                Some(M) => A * &precon::LU_solve(M, &v),
+                Some(M) => A * &M.apply(&v),
                 None => A * &v
             };
             // z = A * &v;
@@ -203,7 +379,7 @@ pub fn HGMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, pre
 
         //* right preconditioning x = x + M_inv * z
         x = match &P {
-            Some(M) => &x + &precon::LU_solve(M, &z),
+            Some(M) => &x + &M.apply(&z),
             None => &x + &z
         };
         // x += &z;
@@ -211,34 +387,21 @@ pub fn HGMRES(iMax: usize, tol: f64, restart: usize, A: &Matrix, b: &Vector, pre
         // println!("iteration: {}, residual: {:.4E}", self.iter, g[H.len()].abs() / bl);
         iter += 1;
         residual = g[H.len()].abs() / bl;
+        history.push(residual);
     } 
 
-    let log = Log {
-        solver: "HGMRES",
-        precon: preconditioner,
-        restart: Some(restart),
-        iMax,
-        iter,
-        residual
-    };
-
-    display(log);
-
-    // let mut print = format!(
-    //     "MSolver: HGMRES({}) {} iteration: {:5}, residual: {:.4E}",
-    //     restart,
-    //     "-".repeat(10),
-    //     iter,
-    //     residual
-    // );
-
-    // if iter == iMax {
-    //     print.push_str(", ***** maximum iteration exceeded!");
-    // }
-
-    // println!("{print}");
-
-    x
+    let reason = stop_reason(residual, tol, None);
+
+    SolveResult {
+        solver: label("HGMRES", Some(restart), &preconditioner),
+        solution: x,
+        iterations: iter,
+        residual,
+        converged: reason == StopReason::Converged,
+        history,
+        elapsed: time.elapsed(),
+        reason
+    }
 }
 
 fn Householder_vec(i: usize, v: &Vector) -> Option<Vector> {
@@ -299,22 +462,141 @@ fn upper_triangular_solve(H: &Vec<Vec<f64>>, g: &Vec<f64>) -> Vec<f64> {
 }
 
 //-----------------------------------------------------------------------------------------------------------//
-pub fn CG(iMax: usize, tol: f64, A: &Matrix, b:&Vector) -> Vector {
+pub fn BiCGSTAB(iMax: usize, tol: f64, A: &Matrix, b: &Vector, preconditioner: Preconditioner, x0: Option<&Vector>) -> SolveResult {
+    // biconjugate gradient stabilized solver with right preconditioning
+    assert!(A.num_cols() == b.num_rows());
+
+    let m = b.num_rows();
+    let bl = b.l2_norm();
+
+    if bl == 0f64 {
+        return zero_rhs(label("BiCGSTAB", None, &preconditioner), m);
+    }
+    let mut iter = 0;
+    let mut x = initial_guess(m, x0);
+    let P = preconditioner.from(A);
+    let mut history = Vec::new();
+    let time = Instant::now();
+    let mut r = b - &(A * &x);
+    let mut residual = r.l2_norm() / bl;
+    let r0 = r.clone();
+    let r0l = r0.l2_norm();
+    let mut p = Vector::from(vec![0.0; m]);
+    let mut v = Vector::from(vec![0.0; m]);
+    let mut rho_old = 1.0;
+    let mut alpha = 1.0;
+    let mut omega = 1.0;
+    let mut breakdown = None;
+
+    while iter < iMax && residual > tol {
+        let rho = &r0 * &r;
+
+        if rho.abs() <= f64::EPSILON * r0l * r.l2_norm() {
+            breakdown = Some("rho is close to zero");
+            break;
+        }
+
+        //* p = r + beta * (p - omega * v)
+        let beta = (rho / rho_old) * (alpha / omega);
+        p = &r + &(beta * &(&p - &(omega * &v)));
+
+        //* right preconditioning v = A * M_inv * p
+        let p_hat = match &P {
+            Some(M) => M.apply(&p),
+            None => p.clone()
+        };
+        v = A * &p_hat;
+
+        let r0v = &r0 * &v;
+        if r0v.abs() <= f64::EPSILON * r0l * v.l2_norm() {
+            breakdown = Some("(r0, v) is close to zero");
+            break;
+        }
+
+        alpha = rho / r0v;
+        let s = &r - &(alpha * &v);
+
+        iter += 1;
+        residual = s.l2_norm() / bl;
+
+        if residual <= tol {
+            history.push(residual);
+            x += &(alpha * &p_hat);
+            break;
+        }
+
+        //* right preconditioning t = A * M_inv * s
+        let s_hat = match &P {
+            Some(M) => M.apply(&s),
+            None => s.clone()
+        };
+        let t = A * &s_hat;
+
+        //* omega = (t, s) / (t, t), relative to |t| |s| as the scale of A cancels
+        let ts = &t * &s;
+        let tl = t.l2_norm();
+        if ts.abs() <= f64::EPSILON * tl * s.l2_norm() {
+            breakdown = Some("omega is close to zero");
+            history.push(residual);
+            x += &(alpha * &p_hat);
+            break;
+        }
+
+        omega = ts / (tl * tl);
+        x += &(alpha * &p_hat);
+        x += &(omega * &s_hat);
+        r = &s - &(omega * &t);
+
+        residual = r.l2_norm() / bl;
+        history.push(residual);
+        rho_old = rho;
+    }
+
+    let reason = stop_reason(residual, tol, breakdown);
+
+    SolveResult {
+        solver: label("BiCGSTAB", None, &preconditioner),
+        solution: x,
+        iterations: iter,
+        residual,
+        converged: reason == StopReason::Converged,
+        history,
+        elapsed: time.elapsed(),
+        reason
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn CG<M: LinearOperator>(iMax: usize, tol: f64, A: &M, b: &Vector, x0: Option<&Vector>) -> SolveResult {
     // conjugate gradient solver
     assert!(A.num_cols() == b.num_rows());        
     
     let m = b.num_rows();
-    let bl =  b.l2_norm();
+    let bl = b.l2_norm();
+
+    if bl == 0f64 {
+        return zero_rhs(label("CG", None, &Preconditioner::None), m);
+    }
     let mut iter = 0;
-    let mut residual = f64::MAX;
-    let mut x = Vector::from(vec![0.0; m]);
-    let mut r = b - &(A * &x);
+    let mut x = initial_guess(m, x0);
+    let mut history = Vec::new();
+    let time = Instant::now();
+    let mut r = b - &A.spmv(&x);
+    let mut residual = r.l2_norm() / bl;
     let mut p = Vector::from(r.clone());
     let mut rsold = &r * &r;
+    let mut breakdown = None;
 
     while iter < iMax && residual > tol {
-        let Ap = A * &p;
-        let alpha = rsold / (&p * &Ap);
+        let Ap = A.spmv(&p);
+        let pAp = &p * &Ap;
+
+        if pAp <= 0f64 {
+            breakdown = Some("(p, Ap) is not positive, the matrix is indefinite");
+            break;
+        }
+
+        let alpha = rsold / pAp;
         
         x += &(alpha * &p);
         r -= &(alpha * &Ap);
@@ -322,6 +604,7 @@ pub fn CG(iMax: usize, tol: f64, A: &Matrix, b:&Vector) -> Vector {
         let rsnew = &r * &r;
 
         residual = rsnew.sqrt() / bl;
+        history.push(residual);
         // residual = (b - &(A * &x)).l2_norm() / bl;
 
         p = &r + &((rsnew / rsold) * &p);
@@ -330,33 +613,118 @@ pub fn CG(iMax: usize, tol: f64, A: &Matrix, b:&Vector) -> Vector {
         iter += 1;
     }
     
-    let mut print = format!(
-        "MSolver: CG {} iteration: {:5}, residual: {:.4E}",
-        "-".repeat(10),
-        iter,
-        residual
-    );
+    let reason = stop_reason(residual, tol, breakdown);
+
+    SolveResult {
+        solver: label("CG", None, &Preconditioner::None),
+        solution: x,
+        iterations: iter,
+        residual,
+        converged: reason == StopReason::Converged,
+        history,
+        elapsed: time.elapsed(),
+        reason
+    }
+}
 
-    if iter == iMax {
-        print.push_str(", ***** maximum iteration exceeded!");
+//-----------------------------------------------------------------------------------------------------------//
+pub fn PCG<M: LinearOperator>(iMax: usize, tol: f64, A: &M, b: &Vector, preconditioner: Preconditioner, x0: Option<&Vector>) -> SolveResult {
+    // preconditioned conjugate gradient solver
+    // the preconditioner shall be symmetric, e.g. SGS or IC
+    // on symmetric storage ILU, ILUK and ILUT are factored from a temporary full matrix
+    assert!(A.num_cols() == b.num_rows());
+
+    let m = b.num_rows();
+    let bl = b.l2_norm();
+
+    if bl == 0f64 {
+        return zero_rhs(label("PCG", None, &preconditioner), m);
     }
-    println!("{print}");
+    let mut iter = 0;
+    let mut x = initial_guess(m, x0);
+    let P = A.precondition(&preconditioner);
+    let mut history = Vec::new();
+    let time = Instant::now();
+    let mut r = b - &A.spmv(&x);
+    let mut residual = r.l2_norm() / bl;
+    let mut z = match &P {
+        Some(M) => M.apply(&r),
+        None => r.clone()
+    };
+    let mut p = z.clone();
+    let mut rzold = &r * &z;
+    let mut breakdown = None;
+
+    while iter < iMax && residual > tol {
+        if rzold <= 0f64 {
+            breakdown = Some("(r, z) is not positive, the preconditioner is indefinite");
+            break;
+        }
+
+        let Ap = A.spmv(&p);
+        let pAp = &p * &Ap;
+
+        if pAp <= 0f64 {
+            breakdown = Some("(p, Ap) is not positive, the matrix is indefinite");
+            break;
+        }
 
-    x
+        let alpha = rzold / pAp;
+
+        x += &(alpha * &p);
+        r -= &(alpha * &Ap);
+
+        iter += 1;
+        residual = r.l2_norm() / bl;
+        history.push(residual);
+
+        if residual <= tol {
+            break;
+        }
+
+        //* z = M_inv * r
+        z = match &P {
+            Some(M) => M.apply(&r),
+            None => r.clone()
+        };
+        let rznew = &r * &z;
+
+        p = &z + &((rznew / rzold) * &p);
+        rzold = rznew;
+    }
+
+    let reason = stop_reason(residual, tol, breakdown);
+
+    SolveResult {
+        solver: label("PCG", None, &preconditioner),
+        solution: x,
+        iterations: iter,
+        residual,
+        converged: reason == StopReason::Converged,
+        history,
+        elapsed: time.elapsed(),
+        reason
+    }
 }
 
 //-----------------------------------------------------------------------------------------------------------//
-pub fn Gauss_Seidel(iMax: usize, tol: f64, A: &Matrix, b: &Vector) -> Vector {
+pub fn Gauss_Seidel(iMax: usize, tol: f64, A: &Matrix, b: &Vector, x0: Option<&Vector>) -> SolveResult {
     assert!(A.num_cols() == b.num_rows());
     
     let m = b.num_rows();
     let bl = b.l2_norm();
+
+    if bl == 0f64 {
+        return zero_rhs(label("Gauss-Seidel", None, &Preconditioner::None), m);
+    }
     let AA = A.AA();
     let JA = A.JA();
     let IA = A.IA();
     let mut iter = 0;
-    let mut residual = f64::MAX;
-    let mut x = Vector::from(vec![0.0; m]);
+    let mut x = initial_guess(m, x0);
+    let mut residual = (b - &(A * &x)).l2_norm() / bl;
+    let mut history = Vec::new();
+    let time = Instant::now();
 
     while iter < iMax && residual > tol {
         for i in 0..m {
@@ -373,60 +741,527 @@ pub fn Gauss_Seidel(iMax: usize, tol: f64, A: &Matrix, b: &Vector) -> Vector {
         }
 
         residual = (b - &(A * &x)).l2_norm() / bl;
+        history.push(residual);
         iter += 1;
     }
 
-    let mut print = format!(
-        "MSolver: Gauss-Seidel {} iteration: {:5}, residual: {:.4E}",
-        "-".repeat(10),
-        iter,
-        residual
-    );
+    let reason = stop_reason(residual, tol, None);
+
+    SolveResult {
+        solver: label("Gauss-Seidel", None, &Preconditioner::None),
+        solution: x,
+        iterations: iter,
+        residual,
+        converged: reason == StopReason::Converged,
+        history,
+        elapsed: time.elapsed(),
+        reason
+    }
+}
 
-    if iter == iMax {
-        print.push_str(", ***** maximum iteration exceeded!");
+//-----------------------------------------------------------------------------------------------------------//
+pub fn SOR(iMax: usize, tol: f64, omega: f64, A: &Matrix, b: &Vector, x0: Option<&Vector>) -> SolveResult {
+    // successive over-relaxation, omega = 1 is Gauss-Seidel
+    assert!(A.num_cols() == b.num_rows());
+    assert!(omega > 0f64 && omega < 2f64, "relaxation parameter omega = {omega} is out of range (0, 2)");
+
+    let m = b.num_rows();
+    let bl = b.l2_norm();
+
+    if bl == 0f64 {
+        return zero_rhs(label(&format!("SOR({omega})"), None, &Preconditioner::None), m);
     }
+    let uptr = A.dia_ptr();
 
-    println!("{print}");
+    if uptr.contains(&usize::MAX) {
+        panic!("diagonal element error");
+    }
+
+    let (AA, JA, IA) = (A.AA(), A.JA(), A.IA());
+    let mut iter = 0;
+    let mut x = initial_guess(m, x0);
+    let mut residual = (b - &(A * &x)).l2_norm() / bl;
+    let mut history = Vec::new();
+    let time = Instant::now();
 
-    x
+    while iter < iMax && residual > tol {
+        for i in 0..m {
+            relax(i, omega, AA, JA, IA, uptr, b, &mut x);
+        }
+
+        residual = (b - &(A * &x)).l2_norm() / bl;
+        history.push(residual);
+        iter += 1;
+    }
+
+    let reason = stop_reason(residual, tol, None);
+
+    SolveResult {
+        solver: label(&format!("SOR({omega})"), None, &Preconditioner::None),
+        solution: x,
+        iterations: iter,
+        residual,
+        converged: reason == StopReason::Converged,
+        history,
+        elapsed: time.elapsed(),
+        reason
+    }
 }
 
 //-----------------------------------------------------------------------------------------------------------//
-struct Log {
-    solver: &'static str,
-    precon: Preconditioner,
-    restart: Option<usize>,
-    iMax: usize,
-    iter: usize,
-    residual: f64
-}
-
-// fn display(solver: &str, preconditioner: Preconditioner, restart: Option<usize>, iter: usize, residual: f64) -> String {
-fn display(log: Log) {
-    let solver = log.solver;
-    let precondition = match log.precon {
-        Preconditioner::GS => " with GS precondition",
-        Preconditioner::SGS => " with SGS precondition",
-        Preconditioner::ILU => " with ILU precondition",
-        _ => ""
+pub fn SSOR(iMax: usize, tol: f64, omega: f64, A: &Matrix, b: &Vector, x0: Option<&Vector>) -> SolveResult {
+    // symmetric SOR, a forward sweep followed by a backward sweep
+    assert!(A.num_cols() == b.num_rows());
+    assert!(omega > 0f64 && omega < 2f64, "relaxation parameter omega = {omega} is out of range (0, 2)");
+
+    let m = b.num_rows();
+    let bl = b.l2_norm();
+
+    if bl == 0f64 {
+        return zero_rhs(label(&format!("SSOR({omega})"), None, &Preconditioner::None), m);
+    }
+    let uptr = A.dia_ptr();
+
+    if uptr.contains(&usize::MAX) {
+        panic!("diagonal element error");
+    }
+
+    let (AA, JA, IA) = (A.AA(), A.JA(), A.IA());
+    let mut iter = 0;
+    let mut x = initial_guess(m, x0);
+    let mut residual = (b - &(A * &x)).l2_norm() / bl;
+    let mut history = Vec::new();
+    let time = Instant::now();
+
+    while iter < iMax && residual > tol {
+        for i in 0..m {
+            relax(i, omega, AA, JA, IA, uptr, b, &mut x);
+        }
+
+        for i in (0..m).rev() {
+            relax(i, omega, AA, JA, IA, uptr, b, &mut x);
+        }
+
+        residual = (b - &(A * &x)).l2_norm() / bl;
+        history.push(residual);
+        iter += 1;
+    }
+
+    let reason = stop_reason(residual, tol, None);
+
+    SolveResult {
+        solver: label(&format!("SSOR({omega})"), None, &Preconditioner::None),
+        solution: x,
+        iterations: iter,
+        residual,
+        converged: reason == StopReason::Converged,
+        history,
+        elapsed: time.elapsed(),
+        reason
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn relax(i: usize, omega: f64, AA: &[f64], JA: &[usize], IA: &[usize], uptr: &[usize], b: &Vector, x: &mut Vector) {
+    // x(i) <- (1 - omega) x(i) + omega (b(i) - sum a(i,j) x(j)) / a(i,i)
+    let mut sum = b[i];
+
+    for j in IA[i]..IA[i+1] {
+        if j != uptr[i] {
+            sum -= AA[j] * x[JA[j]];
+        }
+    }
+
+    x[i] = (1f64 - omega) * x[i] + omega * sum / AA[uptr[i]];
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn Multicolor_SOR(iMax: usize, tol: f64, omega: f64, A: &Matrix, b: &Vector, x0: Option<&Vector>) -> SolveResult {
+    // SOR on the rows reordered by color, the rows of one color are relaxed in parallel
+    // omega = 1 is a Gauss-Seidel with the multicolor ordering
+    assert!(A.num_cols() == b.num_rows());
+    assert!(omega > 0f64 && omega < 2f64, "relaxation parameter omega = {omega} is out of range (0, 2)");
+
+    let m = b.num_rows();
+    let bl = b.l2_norm();
+    let solver = if omega == 1f64 {
+        String::from("Multicolor Gauss-Seidel")
+    } else {
+        format!("Multicolor SOR({omega})")
     };
-    let restart = match log.restart {
+
+    if bl == 0f64 {
+        return zero_rhs(label(&solver, None, &Preconditioner::None), m);
+    }
+    let time = Instant::now();
+
+    //* permutation grouping the rows of each color */
+    let color = A.coloring();
+    let mut perm = (0..m).collect::<Vec<usize>>();
+    perm.par_sort_by_key(|&i| (color[i], i));
+
+    let num_colors = color.iter().max().map_or(0, |&c| c + 1);
+    let mut offset = vec![0usize; num_colors + 1];
+    for &c in &color {
+        offset[c+1] += 1;
+    }
+    for c in 0..num_colors {
+        offset[c+1] += offset[c];
+    }
+
+    let P = A.clone().permutate_par(&perm);
+    let b = b.clone().permutate(&perm);
+    let uptr = P.dia_ptr();
+
+    if uptr.contains(&usize::MAX) {
+        panic!("diagonal element error");
+    }
+
+    let (AA, JA, IA) = (P.AA(), P.JA(), P.IA());
+    let mut iter = 0;
+    let mut x = initial_guess(m, x0).permutate(&perm);
+    let mut residual = (&b - &(&P * &x)).l2_norm() / bl;
+    let mut history = Vec::new();
+
+    while iter < iMax && residual > tol {
+        for c in 0..num_colors {
+            let (start, end) = (offset[c], offset[c+1]);
+            let (left, rest) = x.split_at_mut(start);
+            let (mid, right) = rest.split_at_mut(end - start);
+
+            mid.par_iter_mut().enumerate()
+                .for_each(|(k, xi)| {
+                    let i = start + k;
+                    let mut sum = b[i];
+
+                    for j in IA[i]..IA[i+1] {
+                        let col = JA[j];
+
+                        if col < start {
+                            sum -= AA[j] * left[col];
+                        } else if col >= end {
+                            sum -= AA[j] * right[col - end];
+                        }
+                    }
+
+                    *xi = (1f64 - omega) * *xi + omega * sum / AA[uptr[i]];
+                });
+        }
+
+        residual = (&b - &(&P * &x)).l2_norm() / bl;
+        history.push(residual);
+        iter += 1;
+    }
+
+    //* back to the original ordering */
+    let mut solution = vec![0f64; m];
+    for (k, &i) in perm.iter().enumerate() {
+        solution[i] = x[k];
+    }
+
+    let reason = stop_reason(residual, tol, None);
+
+    SolveResult {
+        solver: label(&solver, None, &Preconditioner::None),
+        solution: Vector::from(solution),
+        iterations: iter,
+        residual,
+        converged: reason == StopReason::Converged,
+        history,
+        elapsed: time.elapsed(),
+        reason
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Converged,
+    MaxIteration,
+    Breakdown(&'static str)
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+#[derive(Debug, Clone)]
+pub struct SolveResult {
+    pub solver: String,
+    pub solution: Vector,
+    pub iterations: usize,
+    pub residual: f64,
+    pub converged: bool,
+    pub history: Vec<f64>,
+    pub elapsed: Duration,
+    pub reason: StopReason
+}
+
+fn zero_rhs(solver: String, m: usize) -> SolveResult {
+    // A x = 0 is solved by x = 0, the relative residual |r| / |b| is not defined
+    SolveResult {
+        solver,
+        solution: Vector::from(vec![0.0; m]),
+        iterations: 0,
+        residual: 0f64,
+        converged: true,
+        history: Vec::new(),
+        elapsed: Duration::ZERO,
+        reason: StopReason::Converged
+    }
+}
+
+fn initial_guess(m: usize, x0: Option<&Vector>) -> Vector {
+    match x0 {
+        Some(x0) => {
+            assert!(x0.num_rows() == m);
+            x0.clone()
+        },
+        None => Vector::from(vec![0.0; m])
+    }
+}
+
+fn stop_reason(residual: f64, tol: f64, breakdown: Option<&'static str>) -> StopReason {
+    if residual <= tol {
+        StopReason::Converged
+    } else if let Some(cause) = breakdown {
+        StopReason::Breakdown(cause)
+    } else if residual.is_nan() {
+        StopReason::Breakdown("residual is not a number")
+    } else {
+        StopReason::MaxIteration
+    }
+}
+
+fn label(solver: &str, restart: Option<usize>, preconditioner: &Preconditioner) -> String {
+    let precondition = match preconditioner {
+        Preconditioner::Jacobi => String::from(" with Jacobi precondition"),
+        Preconditioner::GS => String::from(" with GS precondition"),
+        Preconditioner::SGS => String::from(" with SGS precondition"),
+        Preconditioner::SOR(omega) => format!(" with SOR({omega}) precondition"),
+        Preconditioner::SSOR(omega) => format!(" with SSOR({omega}) precondition"),
+        Preconditioner::ILU => String::from(" with ILU precondition"),
+        Preconditioner::ILUK(k) => format!(" with ILU({k}) precondition"),
+        Preconditioner::ILUT { fill, drop_tol } => format!(" with ILUT({fill}, {drop_tol:E}) precondition"),
+        Preconditioner::IC => String::from(" with IC precondition"),
+        Preconditioner::Custom(_) => String::from(" with custom precondition"),
+        Preconditioner::None => String::new()
+    };
+    let restart = match restart {
         Some(restart) => {
             format!("({restart})")
         },
         None => String::new()
     };
-    let iter = log.iter;
-    let residual = log.residual;
-    let mut format = format!(
-        "MSolver: {solver}{restart}{precondition} {sep} iteration: {iter:5}  residual: {residual:.4E}",
-        sep="-".repeat(10)
-    );
 
-    if iter == log.iMax {
-        format += " ***** warning: maximum iteration exeeded!";
+    format!("{solver}{restart}{precondition}")
+}
+
+impl fmt::Display for SolveResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MSolver: {solver} {sep} iteration: {iter:5}  residual: {residual:.4E}  elapsed: {time:.4} sec",
+            solver = self.solver,
+            sep = "-".repeat(10),
+            iter = self.iterations,
+            residual = self.residual,
+            time = self.elapsed.as_secs_f64()
+        )?;
+
+        match self.reason {
+            StopReason::Converged => Ok(()),
+            StopReason::MaxIteration => write!(f, " ***** warning: maximum iteration exceeded!"),
+            StopReason::Breakdown(cause) => write!(f, " ***** warning: breakdown, {cause}!")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linear_algebra::{preconditioner::optimal_omega, testing::{convection_diffusion, laplacian}};
+
+    fn error(A: &Matrix, result: &SolveResult, b: &Vector) -> f64 {
+        // true relative residual |b - A x| / |b|
+        (b - &(A * &result.solution)).l2_norm() / b.l2_norm()
+    }
+
+    #[test]
+    fn bicgstab_converges_on_convection_diffusion() {
+        let A = convection_diffusion(16, 5.0);
+        let b = A.row_sums();
+
+        for preconditioner in [Preconditioner::None, Preconditioner::ILU] {
+            let result = BiCGSTAB(500, 1.0E-10, &A, &b, preconditioner, None);
+
+            assert_eq!(result.reason, StopReason::Converged, "{}", result.solver);
+            assert!(error(&A, &result, &b) < 1.0E-9, "{}", result.solver);
+        }
+    }
+
+    #[test]
+    fn bicgstab_does_not_depend_on_the_scale_of_a() {
+        let A = convection_diffusion(16, 5.0);
+        let b = A.row_sums();
+        let iterations = BiCGSTAB(500, 1.0E-10, &A, &b, Preconditioner::None, None).iterations;
+
+        for scale in [1.0E-17, 1.0E17] {
+            let result = BiCGSTAB(500, 1.0E-10, &(scale * &A), &(scale * &b), Preconditioner::None, None);
+
+            assert_eq!(result.reason, StopReason::Converged);
+            assert!(result.iterations.abs_diff(iterations) <= 1, "{} and {iterations} iterations", result.iterations);
+        }
+    }
+
+    #[test]
+    fn bicgstab_reports_a_breakdown() {
+        // (r0, A r0) = 0 for a skew-symmetric A
+        let A = Matrix::from_dense([[0.0, 1.0], [-1.0, 0.0]], false);
+        let b = Vector::from(vec![1.0, 0.0]);
+        let result = BiCGSTAB(10, 1.0E-10, &A, &b, Preconditioner::None, None);
+
+        assert_eq!(result.reason, StopReason::Breakdown("(r0, v) is close to zero"));
+        assert!(!result.converged);
+    }
+
+    #[test]
+    fn pcg_with_ic_beats_cg() {
+        let A = laplacian(40, 40);
+        let b = A.row_sums();
+        let cg = CG(1000, 1.0E-10, &A, &b, None);
+        let pcg = PCG(1000, 1.0E-10, &A, &b, Preconditioner::IC, None);
+
+        assert!(cg.converged && pcg.converged);
+        assert!(error(&A, &pcg, &b) < 1.0E-9);
+        assert!(3 * pcg.iterations < 2 * cg.iterations, "PCG {} and CG {} iterations", pcg.iterations, cg.iterations);
+    }
+
+    #[test]
+    fn pcg_reports_indefinite_curvature() {
+        let A = Matrix::from_dense([[1.0, 0.0], [0.0, -2.0]], false);
+        let b = Vector::from(vec![1.0, 1.0]);
+
+        for result in [CG(10, 1.0E-10, &A, &b, None), PCG(10, 1.0E-10, &A, &b, Preconditioner::None, None)] {
+            assert_eq!(result.reason, StopReason::Breakdown("(p, Ap) is not positive, the matrix is indefinite"));
+            assert_eq!(result.iterations, 0);
+        }
     }
 
-    println!("{format}");
-}
\ No newline at end of file
+    #[test]
+    fn sor_and_ssor_converge_on_the_laplacian() {
+        let A = laplacian(20, 20);
+        let b = A.row_sums();
+        let omega = optimal_omega(&A);
+        let gs = Gauss_Seidel(5000, 1.0E-8, &A, &b, None);
+
+        for result in [SOR(5000, 1.0E-8, omega, &A, &b, None), SSOR(5000, 1.0E-8, 1.5, &A, &b, None)] {
+            assert!(result.converged && error(&A, &result, &b) < 1.0E-7, "{}", result.solver);
+            assert!(result.iterations < gs.iterations, "{} in {} iterations", result.solver, result.iterations);
+        }
+
+        // the optimal omega takes far fewer iterations than Gauss-Seidel, omega = 1
+        assert!(4 * SOR(5000, 1.0E-8, omega, &A, &b, None).iterations < gs.iterations);
+    }
+
+    #[test]
+    fn multicolor_sor_reaches_the_sor_solution() {
+        let A = convection_diffusion(20, 1.0);
+        let b = A.row_sums();
+
+        for omega in [1.0, 1.4] {
+            let sor = SOR(5000, 1.0E-10, omega, &A, &b, None);
+            let multicolor = Multicolor_SOR(5000, 1.0E-10, omega, &A, &b, None);
+
+            assert!(sor.converged && multicolor.converged, "{}", multicolor.solver);
+            assert!((&multicolor.solution - &sor.solution).l2_norm() < 1.0E-8 * sor.solution.l2_norm());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range (0, 2)")]
+    fn multicolor_sor_rejects_omega_out_of_range() {
+        let A = laplacian(4, 4);
+        Multicolor_SOR(10, 1.0E-8, 2.0, &A, &A.row_sums(), None);
+    }
+
+    #[test]
+    fn solve_dispatches_through_the_config() {
+        let A = laplacian(12, 12);
+        let b = A.row_sums();
+        let config = SolverConfig::new()
+            .max_iter(2000)
+            .tolerance(Tolerance::Relative(1.0E-8))
+            .restart(20)
+            .preconditioner(Preconditioner::SGS);
+
+        for (solver, name) in [
+            (Solver::GMRES, "GMRES(20) with SGS"), (Solver::HGMRES, "HGMRES(20) with SGS"),
+            (Solver::BiCGSTAB, "BiCGSTAB with SGS"), (Solver::CG, "CG"), (Solver::PCG, "PCG with SGS"),
+            (Solver::GaussSeidel, "Gauss-Seidel"), (Solver::SOR(1.5), "SOR"), (Solver::SSOR(1.5), "SSOR"),
+            (Solver::MulticolorSOR(1.5), "Multicolor")
+        ] {
+            let result = solver.solve(&A, &b, &config);
+
+            assert!(result.solver.starts_with(name), "{} for {solver:?}", result.solver);
+            assert!(result.converged && error(&A, &result, &b) < 1.0E-7, "{}", result.solver);
+        }
+    }
+
+    #[test]
+    fn absolute_tolerance_bounds_the_residual() {
+        let A = laplacian(12, 12);
+        let b = 1.0E4 * &A.row_sums();
+        let config = SolverConfig::new().tolerance(Tolerance::Absolute(1.0E-3));
+        let result = Solver::CG.solve(&A, &b, &config);
+        let relative = Solver::CG.solve(&A, &b, &config.clone().tolerance(Tolerance::Relative(1.0E-3)));
+
+        assert!(result.converged && result.residual <= 1.0E-3 / b.l2_norm());
+        assert!((&b - &(&A * &result.solution)).l2_norm() <= 1.0E-3 * (1.0 + 1.0E-8));
+        assert!(result.iterations > relative.iterations);
+    }
+
+    #[test]
+    fn exact_initial_guess_needs_no_iteration() {
+        let A = laplacian(12, 12);
+        let x = Vector::from((0..A.num_rows()).map(|i| i as f64).collect::<Vec<f64>>());
+        let b = &A * &x;
+        let config = SolverConfig::new().preconditioner(Preconditioner::ILU).initial_guess(x.clone());
+
+        for solver in [
+            Solver::GMRES, Solver::HGMRES, Solver::BiCGSTAB, Solver::CG, Solver::PCG,
+            Solver::GaussSeidel, Solver::SOR(1.5), Solver::SSOR(1.5), Solver::MulticolorSOR(1.5)
+        ] {
+            let result = solver.solve(&A, &b, &config);
+
+            assert!(result.converged, "{}", result.solver);
+            assert_eq!(result.iterations, 0, "{}", result.solver);
+            assert_eq!(result.solution, x, "{}", result.solver);
+        }
+    }
+
+    #[derive(Debug)]
+    struct Identity;
+
+    impl Precondition for Identity {
+        fn apply(&self, r: &Vector) -> Vector {
+            r.clone()
+        }
+    }
+
+    #[test]
+    fn custom_identity_matches_no_preconditioner() {
+        let A = convection_diffusion(12, 2.0);
+        let b = A.row_sums();
+        let identity = SolverConfig::new()
+            .tolerance(Tolerance::Relative(1.0E-10))
+            .preconditioner(Preconditioner::Custom(Arc::new(Identity)));
+        let none = identity.clone().preconditioner(Preconditioner::None);
+
+        for solver in [Solver::GMRES, Solver::HGMRES, Solver::BiCGSTAB, Solver::PCG] {
+            let custom = solver.solve(&A, &b, &identity);
+            let plain = solver.solve(&A, &b, &none);
+
+            assert!(custom.solver.ends_with("with custom precondition"));
+            assert_eq!(custom.iterations, plain.iterations, "{}", custom.solver);
+            assert_eq!(custom.solution, plain.solution, "{}", custom.solver);
+        }
+    }
+}
@@ -0,0 +1,932 @@
+use std::{
+    fmt,
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+use flate2::{
+    Compression,
+    read::MultiGzDecoder,
+    write::GzEncoder,
+};
+use rayon::prelude::*;
+use crate::linear_algebra::{
+    matrix::Matrix,
+    symmetric::SymmetricMatrix,
+    vector::Vector,
+};
+
+#[derive(Debug)]
+pub enum MtxError {
+    MissingFile(PathBuf),
+    Io(io::Error),
+    BadHeader { line: usize, reason: String },
+    UnsupportedField { line: usize, field: String },
+    BadEntry { line: usize, reason: String },
+    CountMismatch { expected: usize, found: usize },
+    IndexOutOfRange { line: usize, row: usize, col: usize },
+    NotSymmetric { row: usize, col: usize }
+}
+
+impl fmt::Display for MtxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MtxError::MissingFile(path) => write!(f, "can not find file {}", path.display()),
+            MtxError::Io(err) => write!(f, "io error: {err}"),
+            MtxError::BadHeader { line, reason } => write!(f, "line {line}: bad header, {reason}"),
+            MtxError::UnsupportedField { line, field } => write!(f, "line {line}: unsupported field '{field}'"),
+            MtxError::BadEntry { line, reason } => write!(f, "line {line}: bad entry, {reason}"),
+            MtxError::CountMismatch { expected, found } => {
+                write!(f, "expected {expected} entries but found {found}")
+            },
+            MtxError::IndexOutOfRange { line, row, col } => {
+                write!(f, "line {line}: index ({row}, {col}) out of range")
+            },
+            MtxError::NotSymmetric { row, col } => {
+                write!(f, "entry ({row}, {col}) has no symmetric counterpart")
+            }
+        }
+    }
+}
+
+impl Error for MtxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MtxError::Io(err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for MtxError {
+    fn from(err: io::Error) -> Self {
+        MtxError::Io(err)
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Coordinate,
+    Array
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Real,
+    Integer,
+    Complex,
+    Pattern
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symmetry {
+    General,
+    Symmetric,
+    SkewSymmetric,
+    Hermitian
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Header {
+    pub format: Format,
+    pub field: Field,
+    pub symmetry: Symmetry
+}
+
+impl Header {
+    fn parse(line: usize, text: &str) -> Result<Header, MtxError> {
+        // %%MatrixMarket matrix <format> <field> <symmetry>
+        let tokens = text.split_whitespace()
+            .map(|token| token.to_lowercase())
+            .collect::<Vec<_>>();
+
+        if tokens.len() != 5 || tokens[0] != "%%matrixmarket" {
+            return Err(MtxError::BadHeader {
+                line,
+                reason: format!("expected '%%MatrixMarket matrix <format> <field> <symmetry>', found '{text}'")
+            });
+        }
+
+        if tokens[1] != "matrix" {
+            return Err(MtxError::BadHeader { line, reason: format!("unknown object '{}'", tokens[1]) });
+        }
+
+        let format = match tokens[2].as_str() {
+            "coordinate" => Format::Coordinate,
+            "array" => Format::Array,
+            _ => return Err(MtxError::UnsupportedField { line, field: tokens[2].clone() })
+        };
+
+        let field = match tokens[3].as_str() {
+            "real" | "double" => Field::Real,
+            "integer" => Field::Integer,
+            "complex" => Field::Complex,
+            "pattern" => Field::Pattern,
+            _ => return Err(MtxError::UnsupportedField { line, field: tokens[3].clone() })
+        };
+
+        let symmetry = match tokens[4].as_str() {
+            "general" => Symmetry::General,
+            "symmetric" => Symmetry::Symmetric,
+            "skew-symmetric" => Symmetry::SkewSymmetric,
+            "hermitian" => Symmetry::Hermitian,
+            _ => return Err(MtxError::UnsupportedField { line, field: tokens[4].clone() })
+        };
+
+        if field == Field::Pattern && format == Format::Array {
+            return Err(MtxError::BadHeader { line, reason: String::from("pattern field requires coordinate format") });
+        }
+
+        if symmetry == Symmetry::Hermitian && field != Field::Complex {
+            return Err(MtxError::BadHeader { line, reason: String::from("hermitian symmetry requires complex field") });
+        }
+
+        if symmetry == Symmetry::SkewSymmetric && field == Field::Pattern {
+            return Err(MtxError::BadHeader { line, reason: String::from("skew-symmetric pattern matrix") });
+        }
+
+        Ok(Header { format, field, symmetry })
+    }
+}
+
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = match self.format {
+            Format::Coordinate => "coordinate",
+            Format::Array => "array"
+        };
+        let field = match self.field {
+            Field::Real => "real",
+            Field::Integer => "integer",
+            Field::Complex => "complex",
+            Field::Pattern => "pattern"
+        };
+        let symmetry = match self.symmetry {
+            Symmetry::General => "general",
+            Symmetry::Symmetric => "symmetric",
+            Symmetry::SkewSymmetric => "skew-symmetric",
+            Symmetry::Hermitian => "hermitian"
+        };
+
+        write!(f, "%%MatrixMarket matrix {format} {field} {symmetry}")
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+struct Reader {
+    // line reader of a plain or gzip compressed Matrix Market file
+    reader: Box<dyn BufRead>,
+    buf: String,
+    line: usize
+}
+
+impl Reader {
+    fn open(path: &Path) -> Result<Reader, MtxError> {
+        if !path.exists() {
+            return Err(MtxError::MissingFile(path.to_path_buf()));
+        }
+
+        let file = File::open(path)?;
+        let reader: Box<dyn BufRead> = if is_gzip(path) {
+            Box::new(BufReader::new(MultiGzDecoder::new(file)))
+        } else {
+            Box::new(BufReader::new(file))
+        };
+
+        Ok(Reader { reader, buf: String::new(), line: 0 })
+    }
+
+    fn next_line(&mut self) -> Result<bool, MtxError> {
+        self.buf.clear();
+
+        if self.reader.read_line(&mut self.buf)? == 0 {
+            return Ok(false);
+        }
+
+        self.line += 1;
+
+        Ok(true)
+    }
+
+    fn next_data(&mut self) -> Result<Option<(usize, &str)>, MtxError> {
+        // next line which is neither a comment nor blank
+        while self.next_line()? {
+            let text = self.buf.trim();
+
+            if !text.is_empty() && !text.starts_with('%') {
+                return Ok(Some((self.line, self.buf.trim())));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn header(&mut self) -> Result<Header, MtxError> {
+        if !self.next_line()? {
+            return Err(MtxError::BadHeader { line: 1, reason: String::from("empty file") });
+        }
+
+        Header::parse(self.line, self.buf.trim())
+    }
+
+    fn size(&mut self, format: Format) -> Result<(usize, usize, usize), MtxError> {
+        // coordinate: rows cols entries, array: rows cols
+        let last = self.line;
+        let (line, text) = match self.next_data()? {
+            Some(data) => data,
+            None => return Err(MtxError::BadHeader { line: last, reason: String::from("missing size line") })
+        };
+        let size = text.split_whitespace()
+            .map(|token| token.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| MtxError::BadHeader { line, reason: format!("bad size line, {err}") })?;
+
+        match (format, &size[..]) {
+            (Format::Coordinate, &[m, n, z]) => Ok((m, n, z)),
+            (Format::Array, &[m, n]) => Ok((m, n, m * n)),
+            (Format::Coordinate, _) => {
+                Err(MtxError::BadHeader { line, reason: format!("expected 'rows cols entries', found '{text}'") })
+            },
+            (Format::Array, _) => {
+                Err(MtxError::BadHeader { line, reason: format!("expected 'rows cols', found '{text}'") })
+            }
+        }
+    }
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+struct Body {
+    // a file positioned at the first entry
+    reader: Reader,
+    header: Header,
+    m: usize,
+    n: usize,
+    z: usize
+}
+
+impl Body {
+    fn open(path: &Path) -> Result<Body, MtxError> {
+        let mut reader = Reader::open(path)?;
+        let header = reader.header()?;
+        let (m, n, z) = reader.size(header.format)?;
+
+        if header.symmetry != Symmetry::General && m != n {
+            return Err(MtxError::BadHeader { line: reader.line, reason: String::from("symmetric storage of a non-square matrix") });
+        }
+
+        Ok(Body { reader, header, m, n, z })
+    }
+
+    fn expected(&self) -> usize {
+        // number of entries listed in the file
+        match (self.header.format, self.header.symmetry) {
+            (Format::Coordinate, _) => self.z,
+            (Format::Array, Symmetry::General) => self.m * self.n,
+            (Format::Array, Symmetry::Symmetric | Symmetry::Hermitian) => self.n * (self.n + 1) / 2,
+            (Format::Array, Symmetry::SkewSymmetric) => self.n * self.n.saturating_sub(1) / 2
+        }
+    }
+
+    fn mirror(&self) -> Option<(f64, f64)> {
+        // sign of (real, imaginary) part of the mirrored entry (j, i)
+        match self.header.symmetry {
+            Symmetry::General => None,
+            Symmetry::Symmetric => Some((1f64, 1f64)),
+            Symmetry::SkewSymmetric => Some((-1f64, -1f64)),
+            Symmetry::Hermitian => Some((1f64, -1f64))
+        }
+    }
+
+    fn for_each(&mut self, mut f: impl FnMut(usize, usize, usize, f64, f64) -> Result<(), MtxError>) -> Result<usize, MtxError> {
+        // visit the zero-based entries (line, i, j, real, imaginary), array zeros are skipped
+        // return the number of entries listed in the file
+        let (m, n) = (self.m, self.n);
+        let header = self.header;
+        let mut found = 0;
+
+        match header.format {
+            Format::Coordinate => {
+                while let Some((line, text)) = self.reader.next_data()? {
+                    let mut tokens = text.split_whitespace();
+                    let i = parse_index(line, tokens.next(), "row index")?;
+                    let j = parse_index(line, tokens.next(), "column index")?;
+                    let (re, im) = parse_value(line, &mut tokens, header.field)?;
+
+                    if i == 0 || i > m || j == 0 || j > n {
+                        return Err(MtxError::IndexOutOfRange { line, row: i, col: j });
+                    }
+
+                    if i == j && header.symmetry == Symmetry::SkewSymmetric {
+                        return Err(MtxError::BadEntry { line, reason: String::from("diagonal entry in a skew-symmetric matrix") });
+                    }
+
+                    f(line, i - 1, j - 1, re, im)?;
+                    found += 1;
+                }
+            },
+            Format::Array => {
+                // column major, only the lower part for the symmetric storages
+                let mut index = (0..n).flat_map(|j| {
+                    let i1 = match header.symmetry {
+                        Symmetry::General => 0,
+                        Symmetry::Symmetric | Symmetry::Hermitian => j,
+                        Symmetry::SkewSymmetric => j + 1
+                    };
+                    (i1..m).map(move |i| (i, j))
+                });
+
+                while let Some((line, text)) = self.reader.next_data()? {
+                    let mut tokens = text.split_whitespace();
+                    let (re, im) = parse_value(line, &mut tokens, header.field)?;
+
+                    if let Some((i, j)) = index.next() {
+                        if re != 0f64 || im != 0f64 {
+                            f(line, i, j, re, im)?;
+                        }
+                    }
+                    found += 1;
+                }
+            }
+        }
+
+        Ok(found)
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+struct Csr {
+    m: usize,
+    n: usize,
+    IA: Vec<usize>,
+    JA: Vec<usize>,
+    AA: Vec<f64>,
+    AI: Option<Vec<f64>>
+}
+
+fn read_csr(path: &Path, complex: bool, lower: bool) -> Result<Csr, MtxError> {
+    // two passes over the file: count the entries of each row, then
+    // scatter them into the CSR arrays. the triples are never stored
+    // with lower set, only the lower triangle of a symmetric matrix is kept
+    let mut body = Body::open(path)?;
+
+    if body.header.field == Field::Complex && !complex {
+        return Err(MtxError::UnsupportedField { line: 1, field: String::from("complex") });
+    }
+
+    if lower {
+        match body.header.symmetry {
+            Symmetry::General => {
+                return Err(MtxError::UnsupportedField { line: 1, field: String::from("general") });
+            },
+            Symmetry::SkewSymmetric => {
+                return Err(MtxError::UnsupportedField { line: 1, field: String::from("skew-symmetric") });
+            },
+            Symmetry::Hermitian => {
+                return Err(MtxError::UnsupportedField { line: 1, field: String::from("hermitian") });
+            },
+            _ => {}
+        }
+
+        if body.m != body.n {
+            return Err(MtxError::BadHeader { line: 1, reason: String::from("symmetric storage needs a square matrix") });
+        }
+    }
+
+    let (m, n) = (body.m, body.n);
+    let expected = body.expected();
+    let mirror = body.mirror();
+
+    //* first pass, number of entries in each row */
+    let mut IA = vec![0usize; m + 1];
+    let found = body.for_each(|_, i, j, _, _| {
+        if lower {
+            // symmetric files may list either triangle
+            IA[i.max(j)+1] += 1;
+            return Ok(());
+        }
+
+        IA[i+1] += 1;
+        if mirror.is_some() && i != j {
+            IA[j+1] += 1;
+        }
+        Ok(())
+    })?;
+
+    if found != expected {
+        return Err(MtxError::CountMismatch { expected, found });
+    }
+
+    for i in 0..m {
+        IA[i+1] += IA[i];
+    }
+
+    //* second pass, entries written to their rows */
+    let nnz = IA[m];
+    let mut JA = vec![0usize; nnz];
+    let mut AA = vec![0f64; nnz];
+    let mut AI = if complex { Some(vec![0f64; nnz]) } else { None };
+    let mut next = IA[..m].to_vec();
+    let mut body = Body::open(path)?;
+
+    let mut push = |line: usize, i: usize, j: usize, re: f64, im: f64| {
+        let k = next[i];
+
+        if k >= IA[i+1] {
+            return Err(MtxError::BadEntry {
+                line,
+                reason: format!("more entries in row {} than counted in the first pass, the file changed while reading", i + 1)
+            });
+        }
+
+        JA[k] = j;
+        AA[k] = re;
+        if let Some(AI) = AI.as_mut() {
+            AI[k] = im;
+        }
+        next[i] += 1;
+
+        Ok(())
+    };
+
+    let found = body.for_each(|line, i, j, re, im| {
+        if lower {
+            return push(line, i.max(j), i.min(j), re, im);
+        }
+
+        push(line, i, j, re, im)?;
+        match mirror {
+            Some((sr, si)) if i != j => push(line, j, i, sr * re, si * im),
+            _ => Ok(())
+        }
+    })?;
+
+    if found != expected {
+        return Err(MtxError::CountMismatch { expected, found });
+    }
+
+    sort_rows(&IA, &mut JA, &mut AA, AI.as_mut());
+
+    Ok(Csr { m, n, IA, JA, AA, AI })
+}
+
+fn sort_rows(IA: &[usize], JA: &mut [usize], AA: &mut [f64], AI: Option<&mut Vec<f64>>) {
+    // sort the columns of each row, rows already in order are left as they are
+    let m = IA.len() - 1;
+    let ja = split_rows(IA, JA);
+    let aa = split_rows(IA, AA);
+    let ai = match AI {
+        Some(AI) => split_rows(IA, AI).into_iter().map(Some).collect::<Vec<_>>(),
+        None => (0..m).map(|_| None).collect::<Vec<_>>()
+    };
+
+    ja.into_par_iter().zip(aa).zip(ai)
+        .for_each(|((ja, aa), ai)| {
+            if ja.windows(2).all(|w| w[0] <= w[1]) {
+                return;
+            }
+
+            let mut argsort = (0..ja.len()).collect::<Vec<_>>();
+            argsort.sort_unstable_by_key(|&k| ja[k]);
+
+            let sorted = argsort.iter().map(|&k| ja[k]).collect::<Vec<_>>();
+            ja.copy_from_slice(&sorted);
+
+            let sorted = argsort.iter().map(|&k| aa[k]).collect::<Vec<_>>();
+            aa.copy_from_slice(&sorted);
+
+            if let Some(ai) = ai {
+                let sorted = argsort.iter().map(|&k| ai[k]).collect::<Vec<_>>();
+                ai.copy_from_slice(&sorted);
+            }
+        });
+}
+
+fn split_rows<'a, T>(IA: &[usize], mut values: &'a mut [T]) -> Vec<&'a mut [T]> {
+    let mut rows = Vec::with_capacity(IA.len() - 1);
+
+    for i in 0..IA.len() - 1 {
+        let (row, rest) = values.split_at_mut(IA[i+1] - IA[i]);
+        rows.push(row);
+        values = rest;
+    }
+
+    rows
+}
+
+fn parse_index(line: usize, token: Option<&str>, name: &str) -> Result<usize, MtxError> {
+    let token = token.ok_or_else(|| MtxError::BadEntry { line, reason: format!("missing {name}") })?;
+
+    token.parse::<usize>()
+        .map_err(|err| MtxError::BadEntry { line, reason: format!("{name} '{token}', {err}") })
+}
+
+fn parse_value<'a>(line: usize, tokens: &mut impl Iterator<Item = &'a str>, field: Field) -> Result<(f64, f64), MtxError> {
+    // (real, imaginary) part of a value, pattern entries are one
+    let mut next = |name: &str| {
+        let token = tokens.next()
+            .ok_or_else(|| MtxError::BadEntry { line, reason: format!("missing {name}") })?;
+
+        token.parse::<f64>()
+            .map_err(|err| MtxError::BadEntry { line, reason: format!("{name} '{token}', {err}") })
+    };
+
+    match field {
+        Field::Pattern => Ok((1f64, 0f64)),
+        Field::Real | Field::Integer => Ok((next("value")?, 0f64)),
+        Field::Complex => Ok((next("real part")?, next("imaginary part")?))
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn read_matrix(path: &Path) -> Result<Matrix, MtxError> {
+    let csr = read_csr(path, false, false)?;
+
+    Ok(Matrix::with_shape(csr.m, csr.n, csr.AA, csr.JA, csr.IA))
+}
+
+pub fn read_symmetric(path: &Path) -> Result<SymmetricMatrix, MtxError> {
+    // lower triangle only, the mirrored entries are never expanded
+    // a general file is read in full and shall be symmetric
+    if Body::open(path)?.header.symmetry == Symmetry::General {
+        let A = read_matrix(path)?;
+
+        if let Some((i, j)) = asymmetric(&A) {
+            return Err(MtxError::NotSymmetric { row: i + 1, col: j + 1 });
+        }
+
+        return Ok(SymmetricMatrix::from_lower(&A));
+    }
+
+    let csr = read_csr(path, false, true)?;
+
+    Ok(SymmetricMatrix::with_lower(Matrix::with_shape(csr.m, csr.n, csr.AA, csr.JA, csr.IA)))
+}
+
+pub fn read_complex(path: &Path) -> Result<(Matrix, Matrix), MtxError> {
+    // real and imaginary parts sharing the same sparsity pattern
+    let csr = read_csr(path, true, false)?;
+    let AI = match csr.AI {
+        Some(AI) => AI,
+        None => vec![0f64; csr.AA.len()]
+    };
+    let imag = Matrix::with_shape(csr.m, csr.n, AI, csr.JA.clone(), csr.IA.clone());
+
+    Ok((Matrix::with_shape(csr.m, csr.n, csr.AA, csr.JA, csr.IA), imag))
+}
+
+pub fn read_vector(path: &Path) -> Result<Vector, MtxError> {
+    // column (m x 1) or row (1 x n) vector in general storage
+    let mut body = Body::open(path)?;
+    let (m, n) = (body.m, body.n);
+
+    if body.header.field == Field::Complex {
+        return Err(MtxError::UnsupportedField { line: 1, field: String::from("complex") });
+    }
+
+    if body.header.symmetry != Symmetry::General || (m != 1 && n != 1) {
+        return Err(MtxError::BadHeader {
+            line: 1,
+            reason: format!("a vector shall be a general m x 1 or 1 x n matrix, found {m} x {n}")
+        });
+    }
+
+    let expected = body.expected();
+    let mut AA = vec![0f64; m.max(n)];
+    let found = body.for_each(|_, i, j, re, _| {
+        AA[i.max(j)] = re;
+        Ok(())
+    })?;
+
+    if found != expected {
+        return Err(MtxError::CountMismatch { expected, found });
+    }
+
+    Ok(Vector::from(AA))
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+fn asymmetric(A: &Matrix) -> Option<(usize, usize)> {
+    // an entry (i, j) without an equal entry (j, i), zero-based
+    let m = A.num_rows();
+    let (AA, JA, IA) = (A.AA(), A.JA(), A.IA());
+
+    (0..m).into_par_iter().find_map_any(|i| {
+        (IA[i]..IA[i+1]).find(|&k| {
+            let j = JA[k];
+            j >= m || match JA[IA[j]..IA[j+1]].binary_search(&i) {
+                Ok(kk) => AA[IA[j] + kk] != AA[k],
+                Err(_) => true
+            }
+        }).map(|k| (i, JA[k]))
+    })
+}
+
+enum Writer {
+    // plain or gzip compressed writer of a Matrix Market file
+    Plain(BufWriter<File>),
+    Gzip(BufWriter<GzEncoder<File>>)
+}
+
+impl Writer {
+    fn create(path: &Path) -> Result<Writer, MtxError> {
+        // the compression is chosen by the file extension
+        let file = File::create(path)?;
+
+        if is_gzip(path) {
+            Ok(Writer::Gzip(BufWriter::new(GzEncoder::new(file, Compression::default()))))
+        } else {
+            Ok(Writer::Plain(BufWriter::new(file)))
+        }
+    }
+
+    fn finish(self) -> Result<(), MtxError> {
+        // flush the buffer and write the gzip trailer, errors are not left to drop
+        match self {
+            Writer::Plain(mut writer) => writer.flush()?,
+            Writer::Gzip(writer) => {
+                let encoder = writer.into_inner().map_err(|err| err.into_error())?;
+                encoder.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Writer::Plain(writer) => writer.write(buf),
+            Writer::Gzip(writer) => writer.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Writer::Plain(writer) => writer.flush(),
+            Writer::Gzip(writer) => writer.flush()
+        }
+    }
+}
+
+pub fn write_matrix(A: &Matrix, path: &Path, symmetry: Symmetry) -> Result<(), MtxError> {
+    // values are written in the shortest form that parses back to the same f64
+    let m = A.num_rows();
+    let n = A.num_cols();
+    let (AA, JA, IA) = (A.AA(), A.JA(), A.IA());
+
+    let lower = match symmetry {
+        Symmetry::General => false,
+        Symmetry::Symmetric => {
+            if let Some((i, j)) = asymmetric(A) {
+                return Err(MtxError::NotSymmetric { row: i + 1, col: j + 1 });
+            }
+
+            true
+        },
+        Symmetry::SkewSymmetric => {
+            return Err(MtxError::UnsupportedField { line: 1, field: String::from("skew-symmetric") });
+        },
+        Symmetry::Hermitian => {
+            return Err(MtxError::UnsupportedField { line: 1, field: String::from("hermitian") });
+        }
+    };
+
+    let z = if lower {
+        (0..m).into_par_iter()
+            .map(|i| JA[IA[i]..IA[i+1]].iter().filter(|&&j| j <= i).count())
+            .sum::<usize>()
+    } else {
+        AA.len()
+    };
+
+    let header = Header { format: Format::Coordinate, field: Field::Real, symmetry };
+    let mut writer = Writer::create(path)?;
+
+    writeln!(writer, "{header}")?;
+    writeln!(writer, "{m} {n} {z}")?;
+
+    for i in 0..m {
+        for k in IA[i]..IA[i+1] {
+            if !lower || JA[k] <= i {
+                writeln!(writer, "{} {} {:e}", i + 1, JA[k] + 1, AA[k])?;
+            }
+        }
+    }
+
+    writer.finish()
+}
+
+pub fn write_vector(v: &Vector, path: &Path) -> Result<(), MtxError> {
+    let header = Header { format: Format::Array, field: Field::Real, symmetry: Symmetry::General };
+    let mut writer = Writer::create(path)?;
+
+    writeln!(writer, "{header}")?;
+    writeln!(writer, "{} 1", v.num_rows())?;
+
+    for value in v.iter() {
+        writeln!(writer, "{value:e}")?;
+    }
+
+    writer.finish()
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("jm_math_{}_{name}", std::process::id()))
+    }
+
+    fn write_text(name: &str, text: &str) -> PathBuf {
+        let path = temp(name);
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn sample() -> Matrix {
+        // nonsymmetric with values which are not exact in decimal
+        Matrix::from_dense(vec![
+            vec![4.0, -1.0 / 3.0, 0.0],
+            vec![0.0, 1.0E-300, 2.5],
+            vec![7.0, 0.0, -0.1]
+        ], false)
+    }
+
+    fn same(A: &Matrix, B: &Matrix) -> bool {
+        A.num_rows() == B.num_rows() && A.num_cols() == B.num_cols()
+            && A.AA() == B.AA() && A.JA() == B.JA() && A.IA() == B.IA()
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        let A = sample();
+
+        for name in ["round_trip.mtx", "round_trip.mtx.gz"] {
+            let path = temp(name);
+            write_matrix(&A, &path, Symmetry::General).unwrap();
+            let B = read_matrix(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert!(same(&A, &B), "{name}");
+        }
+    }
+
+    #[test]
+    fn symmetric_round_trip() {
+        let A = Matrix::from_dense(vec![
+            vec![2.0, -1.0, 0.0],
+            vec![-1.0, 2.0, -0.3],
+            vec![0.0, -0.3, 2.0]
+        ], false);
+
+        for name in ["symmetric.mtx", "symmetric.mtx.gz"] {
+            let path = temp(name);
+            write_matrix(&A, &path, Symmetry::Symmetric).unwrap();
+            let B = read_matrix(&path).unwrap();
+            let S = read_symmetric(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert!(same(&A, &B), "{name}");
+            assert!(same(&S.to_full(), &A), "{name}");
+            assert_eq!(S.lower().AA().len(), 5);
+        }
+
+        let path = temp("not_symmetric.mtx");
+        let result = write_matrix(&sample(), &path, Symmetry::Symmetric);
+        assert!(matches!(result, Err(MtxError::NotSymmetric { .. })));
+    }
+
+    #[test]
+    fn vector_round_trip() {
+        let v = Vector::from(vec![1.0, -2.0 / 3.0, 0.0, 1.0E10]);
+
+        for name in ["vector.mtx", "vector.mtx.gz"] {
+            let path = temp(name);
+            write_vector(&v, &path).unwrap();
+            let w = read_vector(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(v.AA(), w.AA(), "{name}");
+        }
+    }
+
+    #[test]
+    fn symmetric_file_is_expanded() {
+        let path = write_text("expand.mtx", "%%MatrixMarket matrix coordinate real symmetric\n\
+            % comment\n3 3 4\n1 1 2\n2 1 -1\n3 3 5\n2 3 4\n");
+        let A = read_matrix(&path).unwrap();
+        let S = read_symmetric(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(A.to_dense(), vec![
+            vec![2.0, -1.0, 0.0],
+            vec![-1.0, 0.0, 4.0],
+            vec![0.0, 4.0, 5.0]
+        ]);
+        assert!(same(&S.to_full(), &A));
+    }
+
+    fn read_text(name: &str, text: &str) -> Vec<Vec<f64>> {
+        let path = write_text(name, text);
+        let A = read_matrix(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        A.unwrap().to_dense()
+    }
+
+    #[test]
+    fn pattern_and_integer_fields() {
+        let pattern = read_text("pattern.mtx", "%%MatrixMarket matrix coordinate pattern general\n\
+            2 3 3\n1 1\n1 3\n2 2\n");
+        let integer = read_text("integer.mtx", "%%MatrixMarket matrix coordinate integer symmetric\n\
+            2 2 2\n1 1 -3\n2 1 7\n");
+
+        assert_eq!(pattern, vec![vec![1.0, 0.0, 1.0], vec![0.0, 1.0, 0.0]]);
+        assert_eq!(integer, vec![vec![-3.0, 7.0], vec![7.0, 0.0]]);
+    }
+
+    #[test]
+    fn skew_symmetric_file_is_negated() {
+        let A = read_text("skew.mtx", "%%MatrixMarket matrix coordinate real skew-symmetric\n\
+            3 3 2\n2 1 1.5\n3 2 -2\n");
+
+        assert_eq!(A, vec![
+            vec![0.0, -1.5, 0.0],
+            vec![1.5, 0.0, 2.0],
+            vec![0.0, -2.0, 0.0]
+        ]);
+    }
+
+    #[test]
+    fn hermitian_file_is_conjugated() {
+        let path = write_text("hermitian.mtx", "%%MatrixMarket matrix coordinate complex hermitian\n\
+            2 2 3\n1 1 2 0\n2 1 1 -3\n2 2 4 0\n");
+        let (re, im) = read_complex(&path).unwrap();
+        let real = read_matrix(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(re.to_dense(), vec![vec![2.0, 1.0], vec![1.0, 4.0]]);
+        assert_eq!(im.to_dense(), vec![vec![0.0, 3.0], vec![-3.0, 0.0]]);
+        assert!(matches!(real, Err(MtxError::UnsupportedField { .. })));
+    }
+
+    #[test]
+    fn array_files_are_column_major() {
+        let general = read_text("array.mtx", "%%MatrixMarket matrix array real general\n\
+            2 3\n1\n4\n2\n0\n3\n6\n");
+        let symmetric = read_text("array_symmetric.mtx", "%%MatrixMarket matrix array real symmetric\n\
+            3 3\n1\n2\n0\n4\n5\n6\n");
+
+        assert_eq!(general, vec![vec![1.0, 2.0, 3.0], vec![4.0, 0.0, 6.0]]);
+        assert_eq!(symmetric, vec![
+            vec![1.0, 2.0, 0.0],
+            vec![2.0, 4.0, 5.0],
+            vec![0.0, 5.0, 6.0]
+        ]);
+    }
+
+    #[test]
+    fn general_file_in_symmetric_storage() {
+        let symmetric = write_text("general_symmetric.mtx", "%%MatrixMarket matrix coordinate real general\n\
+            2 2 4\n1 1 1\n1 2 3\n2 1 3\n2 2 1\n");
+        let general = write_text("general.mtx", "%%MatrixMarket matrix coordinate real general\n\
+            2 2 3\n1 1 1\n1 2 3\n2 2 1\n");
+
+        let S = read_symmetric(&symmetric);
+        let G = read_symmetric(&general);
+        std::fs::remove_file(&symmetric).unwrap();
+        std::fs::remove_file(&general).unwrap();
+
+        assert_eq!(S.unwrap().lower().AA(), &vec![1.0, 3.0, 1.0]);
+        assert!(matches!(G, Err(MtxError::NotSymmetric { row: 1, col: 2 })));
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let bad_entry = write_text("bad_entry.mtx", "%%MatrixMarket matrix coordinate real general\n\
+            2 2 2\n1 1 1\n2 x 1\n");
+        let out_of_range = write_text("out_of_range.mtx", "%%MatrixMarket matrix coordinate real general\n\
+            % comment\n2 2 1\n3 1 1\n");
+        let count = write_text("count.mtx", "%%MatrixMarket matrix coordinate real general\n\
+            2 2 3\n1 1 1\n2 2 1\n");
+
+        let results = [read_matrix(&bad_entry), read_matrix(&out_of_range), read_matrix(&count)];
+        for path in [bad_entry, out_of_range, count] {
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        assert!(matches!(results[0], Err(MtxError::BadEntry { line: 4, .. })));
+        assert!(matches!(results[1], Err(MtxError::IndexOutOfRange { line: 4, row: 3, col: 1 })));
+        assert!(matches!(results[2], Err(MtxError::CountMismatch { expected: 3, found: 2 })));
+        assert!(matches!(read_matrix(&temp("missing.mtx")), Err(MtxError::MissingFile(_))));
+    }
+}
@@ -0,0 +1,566 @@
+use std::collections::{BTreeSet, VecDeque};
+use rayon::prelude::*;
+use crate::linear_algebra::matrix::Matrix;
+
+// coarsening stops below this number of vertices
+const COARSEST: usize = 64;
+// nested dissection orders smaller subgraphs by AMD
+const LEAF: usize = 64;
+// allowed deviation of a part from its target weight
+const IMBALANCE: f64 = 0.03;
+
+#[derive(Debug, Clone)]
+pub struct Bisection {
+    pub part: Vec<usize>,
+    pub separator: Vec<usize>
+}
+
+/***********************************************************************************************************/
+#[derive(Debug, Clone)]
+struct Graph {
+    xadj: Vec<usize>,
+    adjncy: Vec<usize>,
+    ewgt: Vec<usize>,
+    vwgt: Vec<usize>
+}
+
+impl Graph {
+    fn from_matrix(A: &Matrix) -> Self {
+        // adjacency of A + A^T without self loops, unit weights
+        assert!(A.num_rows() == A.num_cols());
+
+        let G = A + &A.transpose();
+        let rows = (0..G.num_rows()).into_par_iter()
+            .map(|i| {
+                G.neighbor(i).into_iter()
+                    .filter(|&j| j != i)
+                    .map(|j| (j, 1))
+                    .collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+
+        Graph::from_rows(rows, vec![1; G.num_rows()])
+    }
+
+    fn from_rows(rows: Vec<Vec<(usize, usize)>>, vwgt: Vec<usize>) -> Self {
+        let mut xadj = Vec::with_capacity(rows.len() + 1);
+        xadj.push(0);
+        for row in &rows {
+            xadj.push(xadj.last().unwrap() + row.len());
+        }
+
+        let adjncy = rows.par_iter()
+            .map(|row| row.iter().map(|&(j, _)| j).collect::<Vec<_>>())
+            .flatten().collect::<Vec<_>>();
+        let ewgt = rows.par_iter()
+            .map(|row| row.iter().map(|&(_, w)| w).collect::<Vec<_>>())
+            .flatten().collect::<Vec<_>>();
+
+        Graph {
+            xadj,
+            adjncy,
+            ewgt,
+            vwgt
+        }
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn num_vertices(&self) -> usize {
+        self.vwgt.len()
+    }
+
+    fn edges(&self, v: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.xadj[v]..self.xadj[v+1]).map(|k| (self.adjncy[k], self.ewgt[k]))
+    }
+
+    fn subgraph(&self, vertices: &[usize]) -> Graph {
+        // induced subgraph, vertex k of the subgraph is vertices[k]
+        let mut local = vec![usize::MAX; self.num_vertices()];
+        for (k, &v) in vertices.iter().enumerate() {
+            local[v] = k;
+        }
+
+        let rows = vertices.par_iter()
+            .map(|&v| {
+                self.edges(v)
+                    .filter(|&(u, _)| local[u] != usize::MAX)
+                    .map(|(u, w)| (local[u], w))
+                    .collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+
+        Graph::from_rows(rows, vertices.iter().map(|&v| self.vwgt[v]).collect())
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn coarsen(&self) -> (Graph, Vec<usize>) {
+        // heavy edge matching, low degree vertices are matched first
+        // return the coarse graph and the coarse vertex of each fine vertex
+        let n = self.num_vertices();
+        let mut order = (0..n).collect::<Vec<usize>>();
+        order.sort_by_key(|&v| (self.xadj[v+1] - self.xadj[v], v));
+
+        let mut mate = vec![usize::MAX; n];
+        for v in order {
+            if mate[v] != usize::MAX {
+                continue;
+            }
+
+            let heaviest = self.edges(v)
+                .filter(|&(u, _)| mate[u] == usize::MAX && u != v)
+                .max_by_key(|&(u, w)| (w, usize::MAX - u));
+
+            match heaviest {
+                Some((u, _)) => {
+                    mate[v] = u;
+                    mate[u] = v;
+                },
+                None => mate[v] = v
+            }
+        }
+
+        let mut cmap = vec![usize::MAX; n];
+        let mut fine = Vec::new();
+        for v in 0..n {
+            if cmap[v] == usize::MAX {
+                cmap[v] = fine.len();
+                cmap[mate[v]] = fine.len();
+                fine.push((v, mate[v]));
+            }
+        }
+
+        let nc = fine.len();
+        let rows = fine.par_iter().enumerate()
+            .map_init(|| vec![usize::MAX; nc], |pos, (c, &(v, u))| {
+                let mut row: Vec<(usize, usize)> = Vec::new();
+                let vertices = if u == v { vec![v] } else { vec![v, u] };
+
+                for x in vertices {
+                    for (y, w) in self.edges(x) {
+                        let d = cmap[y];
+
+                        if d == c {
+                            continue;
+                        }
+
+                        if pos[d] == usize::MAX {
+                            pos[d] = row.len();
+                            row.push((d, w));
+                        } else {
+                            row[pos[d]].1 += w;
+                        }
+                    }
+                }
+
+                for &(d, _) in &row {
+                    pos[d] = usize::MAX;
+                }
+
+                row
+            }).collect::<Vec<_>>();
+
+        let vwgt = fine.iter()
+            .map(|&(v, u)| if u == v { self.vwgt[v] } else { self.vwgt[v] + self.vwgt[u] })
+            .collect();
+
+        (Graph::from_rows(rows, vwgt), cmap)
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn cut(&self, part: &[usize]) -> usize {
+        (0..self.num_vertices())
+            .map(|v| self.edges(v).filter(|&(u, _)| part[u] != part[v]).map(|(_, w)| w).sum::<usize>())
+            .sum::<usize>() / 2
+    }
+
+    fn grow(&self, seed: usize, target: usize) -> Vec<usize> {
+        // breadth first growth of part 0 from a seed until it reaches the target weight
+        // the search restarts from an unvisited vertex when a component is exhausted
+        let n = self.num_vertices();
+        let mut part = vec![1usize; n];
+        let mut weight = 0;
+        let mut queue = VecDeque::new();
+        let mut next = 0;
+
+        queue.push_back(seed);
+        part[seed] = 0;
+
+        while weight < target {
+            let v = match queue.pop_front() {
+                Some(v) => v,
+                None => {
+                    while next < n && part[next] == 0 {
+                        next += 1;
+                    }
+                    if next == n {
+                        break;
+                    }
+                    part[next] = 0;
+                    next
+                }
+            };
+
+            weight += self.vwgt[v];
+
+            for (u, _) in self.edges(v) {
+                if part[u] == 1 {
+                    part[u] = 0;
+                    queue.push_back(u);
+                }
+            }
+        }
+
+        // vertices queued but not counted stay in part 1
+        for v in queue {
+            part[v] = 1;
+        }
+
+        part
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn refine(&self, part: &mut [usize], target: usize) {
+        // Fiduccia-Mattheyses passes on boundary vertices, each pass is rolled back
+        // to its best state. target is the desired weight of part 0
+        let n = self.num_vertices();
+        let total = self.vwgt.iter().sum::<usize>();
+        let heaviest = self.vwgt.iter().cloned().max().unwrap_or(0);
+        let tol = usize::max((IMBALANCE * total as f64) as usize, heaviest);
+
+        for _ in 0..8 {
+            let mut gain = (0..n)
+                .map(|v| {
+                    self.edges(v)
+                        .map(|(u, w)| if part[u] != part[v] { w as isize } else { -(w as isize) })
+                        .sum::<isize>()
+                }).collect::<Vec<isize>>();
+            let boundary = |v: usize, part: &[usize]| self.edges(v).any(|(u, _)| part[u] != part[v]);
+
+            let mut queue = [BTreeSet::new(), BTreeSet::new()];
+            for v in 0..n {
+                if boundary(v, part) {
+                    queue[part[v]].insert((gain[v], v));
+                }
+            }
+
+            let mut weight = [0usize; 2];
+            for v in 0..n {
+                weight[part[v]] += self.vwgt[v];
+            }
+
+            let imbalance = |weight: &[usize; 2]| weight[0].abs_diff(target);
+            let mut locked = vec![false; n];
+            let mut cut = self.cut(part) as isize;
+            let (mut best_cut, mut best_imbalance) = (cut, imbalance(&weight));
+            let mut moves = Vec::new();
+            let mut best_moves = 0;
+
+            loop {
+                //* move from the heavier side, or the best gain which keeps the balance */
+                let candidate = |side: usize, queue: &[BTreeSet<(isize, usize)>; 2]| queue[side].last().cloned();
+                let from = if weight[0] > target + tol {
+                    0
+                } else if weight[0] + tol < target {
+                    1
+                } else {
+                    match (candidate(0, &queue), candidate(1, &queue)) {
+                        (Some(a), Some(b)) => {
+                            let fits = |side: usize, v: usize| {
+                                let w0 = if side == 0 { weight[0] - self.vwgt[v] } else { weight[0] + self.vwgt[v] };
+                                w0.abs_diff(target) <= tol
+                            };
+                            match (fits(0, a.1), fits(1, b.1)) {
+                                (true, true) => if a.0 >= b.0 { 0 } else { 1 },
+                                (true, false) => 0,
+                                (false, true) => 1,
+                                (false, false) => break
+                            }
+                        },
+                        (Some(_), None) => 0,
+                        (None, Some(_)) => 1,
+                        (None, None) => break
+                    }
+                };
+
+                let (g, v) = match queue[from].pop_last() {
+                    Some(entry) => entry,
+                    None => break
+                };
+
+                //* move v to the other side and update the gains of its neighbours */
+                let to = 1 - from;
+                part[v] = to;
+                locked[v] = true;
+                weight[from] -= self.vwgt[v];
+                weight[to] += self.vwgt[v];
+                cut -= g;
+                moves.push(v);
+
+                for (u, w) in self.edges(v) {
+                    if locked[u] {
+                        continue;
+                    }
+
+                    queue[part[u]].remove(&(gain[u], u));
+                    gain[u] += if part[u] == to { -2 * w as isize } else { 2 * w as isize };
+
+                    if boundary(u, part) {
+                        queue[part[u]].insert((gain[u], u));
+                    }
+                }
+
+                let balance = imbalance(&weight);
+                let better = if balance <= tol {
+                    best_imbalance > tol || cut < best_cut
+                } else {
+                    balance < best_imbalance
+                };
+
+                if better {
+                    best_cut = cut;
+                    best_imbalance = balance;
+                    best_moves = moves.len();
+                }
+
+                if moves.len() - best_moves > 64 {
+                    break;
+                }
+            }
+
+            //* roll back to the best state of the pass */
+            for &v in &moves[best_moves..] {
+                part[v] = 1 - part[v];
+            }
+
+            if best_moves == 0 {
+                break;
+            }
+        }
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn bisect(&self, ratio: f64) -> Vec<usize> {
+        // multilevel bisection, part 0 takes ratio of the total vertex weight
+        let mut levels: Vec<(Graph, Vec<usize>)> = Vec::new();
+        let mut graph = self.clone();
+
+        while graph.num_vertices() > COARSEST {
+            let (coarse, cmap) = graph.coarsen();
+
+            if coarse.num_vertices() as f64 > 0.9 * graph.num_vertices() as f64 {
+                break;
+            }
+
+            levels.push((graph, cmap));
+            graph = coarse;
+        }
+
+        //* initial bisection on the coarsest graph, best of a few seeds */
+        let n = graph.num_vertices();
+        let total = graph.vwgt.iter().sum::<usize>();
+        let target = (ratio * total as f64).round() as usize;
+
+        let mut part = (0..4.min(n))
+            .map(|s| {
+                let mut part = graph.grow(s * n / 4, target);
+                graph.refine(&mut part, target);
+                part
+            })
+            .min_by_key(|part| graph.cut(part))
+            .unwrap_or_default();
+
+        //* project back and refine on each finer level */
+        while let Some((fine, cmap)) = levels.pop() {
+            part = cmap.iter().map(|&c| part[c]).collect();
+
+            let total = fine.vwgt.iter().sum::<usize>();
+            fine.refine(&mut part, (ratio * total as f64).round() as usize);
+        }
+
+        part
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn separate(&self, part: &mut [usize]) -> Vec<usize> {
+        // vertex separator from an edge bisection, the boundary of the lighter side
+        // separator vertices are labelled 2
+        let n = self.num_vertices();
+        let boundary = |side: usize| {
+            (0..n)
+                .filter(|&v| part[v] == side && self.edges(v).any(|(u, _)| part[u] != side))
+                .collect::<Vec<usize>>()
+        };
+
+        let b0 = boundary(0);
+        let b1 = boundary(1);
+        let weight = |b: &Vec<usize>| b.iter().map(|&v| self.vwgt[v]).sum::<usize>();
+        let separator = if weight(&b0) <= weight(&b1) { b0 } else { b1 };
+
+        for &v in &separator {
+            part[v] = 2;
+        }
+
+        separator
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn split(&self, vertices: &[usize], k: usize, first: usize) -> Vec<(usize, usize)> {
+        // recursive bisection into k parts labelled first..first+k
+        if k <= 1 || vertices.len() <= 1 {
+            return vertices.iter().map(|&v| (v, first)).collect();
+        }
+
+        let k0 = k / 2;
+        let part = self.bisect(k0 as f64 / k as f64);
+        let side = |s: usize| (0..vertices.len()).filter(|&v| part[v] == s).collect::<Vec<usize>>();
+        let (p0, p1) = (side(0), side(1));
+
+        let (mut l0, l1) = rayon::join(
+            || self.subgraph(&p0).split(&p0.iter().map(|&v| vertices[v]).collect::<Vec<_>>(), k0, first),
+            || self.subgraph(&p1).split(&p1.iter().map(|&v| vertices[v]).collect::<Vec<_>>(), k - k0, first + k0)
+        );
+
+        l0.extend(l1);
+        l0
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    fn dissect(&self, vertices: &[usize]) -> Vec<usize> {
+        // nested dissection order of vertices, both halves first and the separator last
+        if vertices.len() > LEAF {
+            let mut part = self.bisect(0.5);
+            let separator = self.separate(&mut part);
+            let side = |s: usize| (0..vertices.len()).filter(|&v| part[v] == s).collect::<Vec<usize>>();
+            let (p0, p1) = (side(0), side(1));
+
+            if !p0.is_empty() && !p1.is_empty() {
+                let (mut o0, o1) = rayon::join(
+                    || self.subgraph(&p0).dissect(&p0.iter().map(|&v| vertices[v]).collect::<Vec<_>>()),
+                    || self.subgraph(&p1).dissect(&p1.iter().map(|&v| vertices[v]).collect::<Vec<_>>())
+                );
+
+                o0.extend(o1);
+                o0.extend(separator.iter().map(|&v| vertices[v]));
+                return o0;
+            }
+        }
+
+        //* small or inseparable subgraphs are ordered by minimum degree */
+        let rows = (0..self.num_vertices())
+            .map(|v| {
+                let mut row = self.edges(v).map(|(u, _)| (u, 1f64)).collect::<Vec<_>>();
+                row.push((v, 1f64));
+                row.sort_by_key(|&(u, _)| u);
+                row
+            }).collect::<Vec<_>>();
+
+        Matrix::from_rows(self.num_vertices(), rows).AMD().into_iter()
+            .map(|v| vertices[v])
+            .collect()
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn bisect(A: &Matrix) -> Bisection {
+    // multilevel bisection of the graph of A + A^T with a vertex separator
+    // part[i] is 0 or 1, or 2 for the vertices of the separator
+    let graph = Graph::from_matrix(A);
+    let mut part = graph.bisect(0.5);
+    let separator = graph.separate(&mut part);
+
+    Bisection {
+        part,
+        separator
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn partition(A: &Matrix, k: usize) -> Vec<usize> {
+    // k-way partition labels of the graph of A + A^T by recursive bisection
+    assert!(k > 0);
+
+    let graph = Graph::from_matrix(A);
+    let vertices = (0..graph.num_vertices()).collect::<Vec<usize>>();
+    let mut labels = vec![0usize; vertices.len()];
+
+    for (v, label) in graph.split(&vertices, k, 0) {
+        labels[v] = label;
+    }
+
+    labels
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn interface(A: &Matrix, labels: &[usize]) -> Vec<usize> {
+    // vertices with a neighbour in another part, the separator set of a k-way partition
+    assert!(A.num_rows() == labels.len());
+
+    let graph = Graph::from_matrix(A);
+
+    (0..graph.num_vertices()).into_par_iter()
+        .filter(|&v| graph.edges(v).any(|(u, _)| labels[u] != labels[v]))
+        .collect()
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn nested_dissection(A: &Matrix) -> Vec<usize> {
+    // fill reducing permutation, compatible with permutate_par
+    let graph = Graph::from_matrix(A);
+    let vertices = (0..graph.num_vertices()).collect::<Vec<usize>>();
+
+    graph.dissect(&vertices)
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linear_algebra::testing::laplacian;
+
+    fn neighbors(A: &Matrix, i: usize) -> impl Iterator<Item = usize> + '_ {
+        A.JA()[A.IA()[i]..A.IA()[i+1]].iter().copied().filter(move |&j| j != i)
+    }
+
+    #[test]
+    fn bisection_separates_the_parts() {
+        let A = laplacian(24, 16);
+        let Bisection { part, separator } = bisect(&A);
+
+        for i in 0..A.num_rows() {
+            for j in neighbors(&A, i) {
+                assert!(part[i] == 2 || part[j] == 2 || part[i] == part[j], "edge ({i}, {j}) crosses the separator");
+            }
+        }
+
+        assert!(separator.iter().all(|&v| part[v] == 2));
+        assert_eq!(part.iter().filter(|&&p| p == 2).count(), separator.len());
+        assert!(part.contains(&0) && part.contains(&1));
+        assert!(separator.len() <= 2 * 16, "separator of {} vertices", separator.len());
+    }
+
+    #[test]
+    fn partition_and_interface() {
+        let A = laplacian(20, 20);
+        let labels = partition(&A, 4);
+
+        for k in 0..4 {
+            let size = labels.iter().filter(|&&l| l == k).count();
+            assert!(size > 50, "part {k} has {size} vertices");
+        }
+
+        let nodes = interface(&A, &labels);
+        for i in 0..A.num_rows() {
+            let crossing = neighbors(&A, i).any(|j| labels[j] != labels[i]);
+            assert_eq!(crossing, nodes.contains(&i));
+        }
+    }
+
+    #[test]
+    fn nested_dissection_is_a_permutation() {
+        let A = laplacian(40, 40);
+        let mut perm = nested_dissection(&A);
+
+        assert_eq!(perm, A.ND());
+
+        perm.sort_unstable();
+        assert_eq!(perm, (0..A.num_rows()).collect::<Vec<usize>>());
+    }
+}
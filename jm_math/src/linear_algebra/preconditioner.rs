@@ -1,28 +1,71 @@
 use core::panic;
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
 use rayon::prelude::*;
 // use std::sync::{Arc, Mutex};
-use crate::linear_algebra::matrix::Matrix;
+use crate::linear_algebra::matrix::{Matrix, LevelSet};
 use crate::linear_algebra::vector::Vector;
+use crate::linear_algebra::symmetric::SymmetricMatrix;
 
+pub trait Precondition: fmt::Debug + Send + Sync {
+    // return z = M_inv * r
+    fn apply(&self, r: &Vector) -> Vector;
+}
+
+impl Precondition for Matrix {
+    // LU factor built by GS, SGS, ILU or IC
+    fn apply(&self, r: &Vector) -> Vector {
+        LU_solve(self, r)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Preconditioner {
     Jacobi,
     GS,
     SGS,
-    // SOR(f64),
-    // SSOR(f64),
+    SOR(f64),
+    SSOR(f64),
     ILU,
+    ILUK(usize),
+    ILUT { fill: usize, drop_tol: f64 },
+    IC,
+    Custom(Arc<dyn Precondition>),
     None
 }
 
 impl Preconditioner {
-    pub fn from(&self, A: &Matrix) -> Option<Matrix> {
+    pub fn from(&self, A: &Matrix) -> Option<Arc<dyn Precondition>> {
         match self {
-            Preconditioner::GS => Some(GS(A)),
-            Preconditioner::SGS => Some(SGS(A)),
-            Preconditioner::ILU => Some(ILU(A)),
-            Preconditioner::None => None,
-            _ => panic!("not available preconditioner")
+            Preconditioner::Jacobi => Some(Arc::new(Jacobi_factor(A))),
+            Preconditioner::GS => Some(Arc::new(GS(A))),
+            Preconditioner::SGS => Some(Arc::new(SGS(A))),
+            Preconditioner::SOR(omega) => Some(Arc::new(SOR(A, *omega))),
+            Preconditioner::SSOR(omega) => Some(Arc::new(SSOR(A, *omega))),
+            Preconditioner::ILU => Some(Arc::new(ILU(A))),
+            Preconditioner::ILUK(k) => Some(Arc::new(ILUK(A, *k))),
+            Preconditioner::ILUT { fill, drop_tol } => Some(Arc::new(ILUT(A, *fill, *drop_tol))),
+            Preconditioner::IC => Some(Arc::new(IC(A))),
+            Preconditioner::Custom(P) => Some(P.clone()),
+            Preconditioner::None => None
+        }
+    }
+
+    pub fn from_symmetric(&self, A: &SymmetricMatrix) -> Option<Arc<dyn Precondition>> {
+        // Jacobi, GS, SOR and IC reference only the lower triangle, SGS and SSOR also its transpose
+        // ILU, ILUK and ILUT need the upper triangle and are factored from a temporary full matrix
+        match self {
+            Preconditioner::Jacobi => Some(Arc::new(Jacobi_factor(A.lower()))),
+            Preconditioner::GS => Some(Arc::new(GS(A.lower()))),
+            Preconditioner::SGS => Some(Arc::new(SSOR_symmetric(A, 1f64))),
+            Preconditioner::SOR(omega) => Some(Arc::new(SOR(A.lower(), *omega))),
+            Preconditioner::SSOR(omega) => Some(Arc::new(SSOR_symmetric(A, *omega))),
+            Preconditioner::ILU | Preconditioner::ILUK(_) | Preconditioner::ILUT { .. } => self.from(&A.to_full()),
+            Preconditioner::IC => Some(Arc::new(IC(A.lower()))),
+            Preconditioner::Custom(P) => Some(P.clone()),
+            Preconditioner::None => None
         }
     }
 }
@@ -36,28 +79,46 @@ pub fn none<'a>(_A: &Matrix, b: &'a Vector) -> &'a Vector {
 //-----------------------------------------------------------------------------------------------------------//
 pub fn Jacobi(A: &Matrix, b: &Vector) -> Vector {
     let m = A.num_rows();
+    let uptr = A.dia_ptr();
     let mut x = Vector::from(vec![0f64; m]);
 
     x.par_iter_mut().enumerate()
         .for_each(|(i, x)| {
-            let j1 = A.IA()[i];
-            let j2 = A.IA()[i+1];
-            let idx = A.JA()[j1..j2].iter().position(|&j| i == j);
-
-            match idx {
-                Some(idx) => {
-                    *x = b[i];
-                    *x /= A.AA()[j1..j2][idx];
-                },
-                None => panic!("can't find the diagonal index")
+            if uptr[i] == usize::MAX {
+                panic!("can't find the diagonal index");
             }
 
-            // let idx = j1;
+            *x = b[i];
+            *x /= A.AA()[uptr[i]];
         });
 
     x
 }
 
+pub fn Jacobi_factor(A: &Matrix) -> Matrix {
+    // Jacobi preconditioner as a diagonal factor in the LU layout of LU_solve
+    check(A);
+    let m = A.num_rows();
+    let uptr = A.dia_ptr();
+
+    let AA = (0..m).into_par_iter()
+        .map(|i| {
+            if A.AA()[uptr[i]] == 0f64 {
+                panic!("diagonal element error");
+            }
+
+            1f64 / A.AA()[uptr[i]]
+        }).collect::<Vec<f64>>();
+    let JA = (0..m).collect::<Vec<usize>>();
+    let IA = (0..m+1).collect::<Vec<usize>>();
+
+    let mut M = Matrix::from(AA, JA.clone(), IA);
+    M.set_dia_ptr(JA);
+    M.set_levels();
+
+    M
+}
+
 //-----------------------------------------------------------------------------------------------------------//
 // pub fn Gauss_Seidel(A: &Matrix, b: &Vector) -> Vector {
 //     let m = A.num_rows();
@@ -97,48 +158,17 @@ pub fn Jacobi(A: &Matrix, b: &Vector) -> Vector {
 pub fn level_schduling(A: &Matrix, b: &Vector) -> Vector {
     // lower part of A
     let m = A.num_rows();
-    let mut depth = vec![0usize; m];
-    let mut uptr = vec![0usize; m];
-    let mut q = (0..m).collect::<Vec<usize>>();
+    let uptr = A.dia_ptr();
     let mut x = Vector::from(vec![0f64; m]);
 
-    for i in 0..m {
-        let j1 = A.IA()[i];
-        let mut j = j1;
-        let mut jrow: usize; 
-
-        loop {
-            jrow = A.JA()[j];
-
-            if jrow >= i {
-                break;
-            }
-
-            j += 1;
-        }
-
-        uptr[i] = j;
-        if jrow != i {
-            panic!("{}", "diagonal element error");
-        }
-
-        // depth for vertices
-        depth[i] = 1 + A.JA()[j1..=j].iter().map(|&i| depth[i]).max().unwrap();
+    if uptr.contains(&usize::MAX) {
+        panic!("{}", "diagonal element error");
     }
 
-    q.par_sort_by_key(|&i| depth[i]);
-
-    let mut tmp = depth[q[0]];
-    let mut level = Vec::with_capacity(*q.last().unwrap());
-
-    level.push(0);
-    for i in 0..q.len() {
-        if depth[q[i]] != tmp {
-            tmp = depth[q[i]];
-            level.push(i); // 비효율 발생
-        }
-    }
-    level.push(q.len());
+    // rows grouped by the depth of their lower dependencies
+    let levels = LevelSet::forward(A, uptr);
+    let q = levels.rows();
+    let level = levels.ptr();
 
     println!("num_levels: {}", level.len() - 1);
     //
@@ -176,10 +206,19 @@ pub fn level_schduling(A: &Matrix, b: &Vector) -> Vector {
     x
 }
 
+//-----------------------------------------------------------------------------------------------------------//
+fn check(A: &Matrix) {
+    // factorizations need sorted columns and a present diagonal in every row
+    if let Err(e) = A.validate() {
+        panic!("invalid matrix for a preconditioner, {e}");
+    }
+}
+
 //-----------------------------------------------------------------------------------------------------------//
 pub fn GS(A: &Matrix) -> Matrix {
     // Gauss Seidel preconditioner
     // return lower part of A as a preconditioner
+    check(A);
     let m = A.num_rows();
     let mut AA = Vec::with_capacity(A.AA().len());
     let mut JA = Vec::with_capacity(A.JA().len());
@@ -226,12 +265,14 @@ pub fn GS(A: &Matrix) -> Matrix {
 
     let mut M = Matrix::from(AA, JA, IA);
     M.set_dia_ptr(UPTR);
+    M.set_levels();
 
     M
 }
 
 //-----------------------------------------------------------------------------------------------------------//
 pub fn SGS(A: &Matrix) -> Matrix {
+    check(A);
     let m = A.num_rows();
     let mut AA = Vec::with_capacity(A.AA().len());
     // let mut JA = Vec::with_capacity(A.JA().len());
@@ -290,12 +331,22 @@ pub fn SGS(A: &Matrix) -> Matrix {
     // let mut M = Matrix::from(AA, JA, IA);
     let mut M = Matrix::from(AA, A.JA().clone(), A.IA().clone());
     M.set_dia_ptr(UPTR);
+    M.set_levels();
 
     M
 }
 
 //-----------------------------------------------------------------------------------------------------------//
 pub fn ILU(A: &Matrix) -> Matrix {
+    check(A);
+
+    ILU_factor(A, false)
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+fn ILU_factor(A: &Matrix, eliminated: bool) -> Matrix {
+    // incomplete LU on the pattern of A. the pivot is the diagonal of A,
+    // or the diagonal after the elimination of the lower elements when eliminated
     let m = A.num_rows();
     let mut AA = A.AA().clone();
     let mut UPTR = vec![0usize; m];
@@ -341,11 +392,13 @@ pub fn ILU(A: &Matrix) -> Matrix {
 
         UPTR[k] = j;
 
-        if jrow != k || A.AA()[j] == 0.0 {
+        let pivot = if eliminated { AA[j] } else { A.AA()[j] };
+
+        if jrow != k || pivot == 0.0 {
             panic!("diagonal element error");
         }
 
-        AA[j] = 1f64 / A.AA()[j];
+        AA[j] = 1f64 / pivot;
         
         for i in j1..j2 {
             IW[A.JA()[i]] = 0;
@@ -354,23 +407,500 @@ pub fn ILU(A: &Matrix) -> Matrix {
 
     let mut M = Matrix::from(AA, A.JA().clone(), A.IA().clone());
     M.set_dia_ptr(UPTR);
+    M.set_levels();
+
+    M
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn ILUK(A: &Matrix, k: usize) -> Matrix {
+    // ILU(k), the pattern of A is extended by the fill-in up to level k
+    // and factored on the extended pattern with the pivots taken after the elimination,
+    // so that LU = A on the pattern. unlike ILU, k = 0 is the textbook ILU(0)
+    check(A);
+    let m = A.num_rows();
+
+    // symbolic factorization, lev(i,j) = min(lev(i,p) + lev(p,j) + 1) over the pivots p
+    // rows[i] holds (column, level, value) in increasing column order
+    let mut rows: Vec<Vec<(usize, usize, f64)>> = Vec::with_capacity(m);
+    let mut UPTR = vec![0usize; m];
+
+    for i in 0..m {
+        let mut row = (A.IA()[i]..A.IA()[i+1])
+            .map(|j| (A.JA()[j], (0usize, A.AA()[j])))
+            .collect::<BTreeMap<usize, (usize, f64)>>();
+
+        let mut pivot = 0;
+        while let Some((&p, &(lp, _))) = row.range(pivot..i).next() {
+            for &(j, lj, _) in &rows[p][UPTR[p]+1..] {
+                let level = lp + lj + 1;
+
+                if level <= k {
+                    row.entry(j)
+                        .and_modify(|e| e.0 = e.0.min(level))
+                        .or_insert((level, 0f64));
+                }
+            }
+
+            pivot = p + 1;
+        }
+
+        let row = row.into_iter()
+            .map(|(j, (level, value))| (j, level, value))
+            .collect::<Vec<_>>();
+
+        UPTR[i] = row.partition_point(|e| e.0 < i);
+        rows.push(row);
+    }
+
+    // numeric factorization on the extended pattern
+    let rows = rows.into_iter()
+        .map(|row| row.into_iter().map(|(j, _, value)| (j, value)).collect())
+        .collect::<Vec<Vec<(usize, f64)>>>();
+
+    ILU_factor(&Matrix::from_rows(A.num_cols(), rows), true)
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn ILUT(A: &Matrix, fill: usize, drop_tol: f64) -> Matrix {
+    // ILUT(p, tau) of Saad, dual threshold incomplete LU
+    // entries below tau * |a(i,:)| are dropped, and at most p largest entries
+    // are kept in each of the lower and the upper part of a row
+    check(A);
+    assert!(drop_tol >= 0f64, "drop tolerance must be non-negative");
+    let m = A.num_rows();
+    let mut AA: Vec<f64> = Vec::with_capacity(A.AA().len());
+    let mut JA: Vec<usize> = Vec::with_capacity(A.JA().len());
+    let mut IA = vec![0usize; m+1];
+    let mut UPTR = vec![0usize; m];
+
+    // working row, IW marks the columns present in w
+    let mut w = vec![0f64; m];
+    let mut IW = vec![false; m];
+
+    for i in 0..m {
+        let j1 = A.IA()[i];
+        let j2 = A.IA()[i+1];
+        let norm = A.AA()[j1..j2].iter().map(|a| a * a).sum::<f64>().sqrt();
+        let tau = drop_tol * norm;
+
+        let mut lower = BTreeSet::new();
+        let mut upper = Vec::new();
+
+        for j in j1..j2 {
+            let jrow = A.JA()[j];
+
+            w[jrow] = A.AA()[j];
+            IW[jrow] = true;
+
+            if jrow < i {
+                lower.insert(jrow);
+            } else if jrow > i {
+                upper.push(jrow);
+            }
+        }
+
+        IW[i] = true;
+
+        // for lower elements, eliminated in increasing column order
+        let mut L = Vec::new();
+        let mut pivot = 0;
+
+        while let Some(&k) = lower.range(pivot..).next() {
+            pivot = k + 1;
+
+            let l = w[k] * AA[UPTR[k]];
+            if l.abs() < tau {
+                continue;
+            }
+
+            for jj in UPTR[k]+1..IA[k+1] {
+                let jrow = JA[jj];
+
+                if !IW[jrow] {
+                    IW[jrow] = true;
+                    w[jrow] = 0f64;
+
+                    if jrow < i {
+                        lower.insert(jrow);
+                    } else {
+                        upper.push(jrow);
+                    }
+                }
+
+                w[jrow] -= l * AA[jj];
+            }
+
+            L.push((k, l));
+        }
+
+        let mut U = upper.iter()
+            .map(|&j| (j, w[j]))
+            .collect::<Vec<_>>();
+
+        // keep the largest entries of each part in increasing column order
+        for part in [&mut L, &mut U] {
+            part.retain(|&(_, v)| v.abs() >= tau);
+            part.sort_unstable_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+            part.truncate(fill);
+            part.sort_unstable_by_key(|&(j, _)| j);
+        }
+
+        // for diagonal elements, a zero pivot is replaced as in Saad's ILUT
+        let mut d = w[i];
+        if d == 0f64 {
+            d = (1.0E-4 + drop_tol) * norm;
+        }
+
+        if d == 0f64 || !d.is_finite() {
+            panic!("diagonal element error");
+        }
+
+        for &(j, l) in &L {
+            AA.push(l);
+            JA.push(j);
+        }
+
+        UPTR[i] = AA.len();
+        AA.push(1f64 / d);
+        JA.push(i);
+
+        for &(j, u) in &U {
+            AA.push(u);
+            JA.push(j);
+        }
+
+        IA[i+1] = AA.len();
+
+        for j in lower.into_iter().chain(upper).chain([i]) {
+            w[j] = 0f64;
+            IW[j] = false;
+        }
+    }
+
+    AA.shrink_to_fit();
+    JA.shrink_to_fit();
+
+    let mut M = Matrix::from(AA, JA, IA);
+    M.set_dia_ptr(UPTR);
+    M.set_levels();
+
+    M
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn IC(A: &Matrix) -> Matrix {
+    // incomplete Cholesky IC(0), A = L D L^T on the lower pattern of A
+    // only the lower part of A is referenced. the factor is returned in the
+    // LU layout of LU_solve: unit lower L, inverted diagonal and U = D L^T
+    check(A);
+
+    // on a non-positive pivot the diagonal of A is shifted, (1 + alpha) * a(i,i)
+    // no shift up to 1E3 works when A has a non-positive diagonal, IC is not applicable then
+    let mut alpha = 0f64;
+
+    loop {
+        match IC_shifted(A, alpha) {
+            Some(M) => return M,
+            None => {
+                alpha = f64::max(2.0 * alpha, 1.0E-3);
+
+                if alpha > 1.0E3 {
+                    panic!("non-positive pivot error");
+                }
+            }
+        }
+    }
+}
+
+fn IC_shifted(A: &Matrix, alpha: f64) -> Option<Matrix> {
+    let m = A.num_rows();
+    let mut LA = Vec::with_capacity(A.AA().len() / 2 + m);
+    let mut LJ = Vec::with_capacity(A.JA().len() / 2 + m);
+    let mut LI = vec![0usize; m+1];
+    let mut D = vec![0f64; m];
+    let mut IW = vec![usize::MAX; m];
+
+    for i in 0..m {
+        let j1 = A.IA()[i];
+        let j2 = A.IA()[i+1];
+        let mut diagonal = None;
+
+        for j in j1..j2 {
+            let jrow = A.JA()[j];
+
+            if jrow < i {
+                IW[jrow] = LA.len();
+                LA.push(A.AA()[j]);
+                LJ.push(jrow);
+            } else if jrow == i {
+                diagonal = Some(A.AA()[j]);
+            }
+        }
+
+        let mut d = match diagonal {
+            Some(d) => (1.0 + alpha) * d,
+            None => panic!("diagonal element error")
+        };
+
+        // l(i,k) = (a(i,k) - sum l(i,j) d(j) l(k,j)) / d(k)
+        for jj in LI[i]..LA.len() {
+            let k = LJ[jj];
+
+            for kk in LI[k]..LI[k+1] {
+                let jw = IW[LJ[kk]];
+                if jw != usize::MAX {
+                    LA[jj] -= LA[jw] * D[LJ[kk]] * LA[kk];
+                }
+            }
+
+            LA[jj] /= D[k];
+            d -= LA[jj] * LA[jj] * D[k];
+        }
+
+        if d <= 0f64 {
+            return None;
+        }
+
+        D[i] = d;
+        LI[i+1] = LA.len();
+
+        for jj in LI[i]..LI[i+1] {
+            IW[LJ[jj]] = usize::MAX;
+        }
+    }
+
+    // upper part U = D L^T, gathered row by row in increasing column order
+    let mut upper = vec![Vec::new(); m];
+    for i in 0..m {
+        for jj in LI[i]..LI[i+1] {
+            let k = LJ[jj];
+            upper[k].push((i, D[k] * LA[jj]));
+        }
+    }
+
+    let mut AA = Vec::with_capacity(2 * LA.len() + m);
+    let mut JA = Vec::with_capacity(2 * LA.len() + m);
+    let mut IA = vec![0usize; m+1];
+    let mut UPTR = vec![0usize; m];
+
+    for i in 0..m {
+        AA.extend_from_slice(&LA[LI[i]..LI[i+1]]);
+        JA.extend_from_slice(&LJ[LI[i]..LI[i+1]]);
+
+        UPTR[i] = AA.len();
+        AA.push(1f64 / D[i]);
+        JA.push(i);
+
+        for &(j, value) in &upper[i] {
+            AA.push(value);
+            JA.push(j);
+        }
+
+        IA[i+1] = AA.len();
+    }
+
+    let mut M = Matrix::from(AA, JA, IA);
+    M.set_dia_ptr(UPTR);
+    M.set_levels();
+
+    Some(M)
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+fn check_omega(omega: f64) {
+    // SOR and SSOR converge for SPD matrices only when 0 < omega < 2
+    if !(omega > 0f64 && omega < 2f64) {
+        panic!("relaxation parameter omega = {omega} is out of range (0, 2)");
+    }
+}
+
+pub fn SOR(A: &Matrix, omega: f64) -> Matrix {
+    // SOR preconditioner, M = D / omega + L
+    // factored as (I + omega L D^-1)(D / omega), omega = 1 is the GS preconditioner
+    check(A);
+    check_omega(omega);
+    let m = A.num_rows();
+    let dia = A.dia_ptr();
+    let mut AA = Vec::with_capacity(A.AA().len());
+    let mut JA = Vec::with_capacity(A.JA().len());
+    let mut IA = vec![0usize; m+1];
+    let mut UPTR = vec![usize::MAX; m];
+
+    for i in 0..m {
+        if A.AA()[dia[i]] == 0f64 {
+            panic!("diagonal element error");
+        }
+
+        // for lower elements
+        for j in A.IA()[i]..dia[i] {
+            let jrow = A.JA()[j];
+
+            AA.push(omega * A.AA()[j] / A.AA()[dia[jrow]]);
+            JA.push(jrow);
+        }
+
+        // for diagonal elements
+        UPTR[i] = AA.len();
+        AA.push(omega / A.AA()[dia[i]]);
+        JA.push(i);
+        IA[i+1] = AA.len();
+    }
+
+    AA.shrink_to_fit();
+    JA.shrink_to_fit();
+
+    let mut M = Matrix::from(AA, JA, IA);
+    M.set_dia_ptr(UPTR);
+    M.set_levels();
+
+    M
+}
+
+//-----------------------------------------------------------------------------------------------------------//
+pub fn SSOR(A: &Matrix, omega: f64) -> Matrix {
+    // SSOR preconditioner, M = (D / omega + L)(D / omega)^-1 (D / omega + U) omega / (2 - omega)
+    // factored as (I + omega L D^-1)(D + omega U) / (2 - omega), omega = 1 is the SGS preconditioner
+    check(A);
+    check_omega(omega);
+    let m = A.num_rows();
+    let dia = A.dia_ptr();
+    let mut AA = Vec::with_capacity(A.AA().len());
+    let mut UPTR = vec![usize::MAX; m];
+
+    for i in 0..m {
+        if A.AA()[dia[i]] == 0f64 {
+            panic!("diagonal element error");
+        }
+
+        // for lower elements
+        for j in A.IA()[i]..dia[i] {
+            AA.push(omega * A.AA()[j] / A.AA()[dia[A.JA()[j]]]);
+        }
+
+        // for diagonal elements
+        UPTR[i] = dia[i];
+        AA.push((2f64 - omega) / A.AA()[dia[i]]);
+
+        // for upper elements
+        for j in dia[i]+1..A.IA()[i+1] {
+            AA.push(omega * A.AA()[j] / (2f64 - omega));
+        }
+    }
+
+    let mut M = Matrix::from(AA, A.JA().clone(), A.IA().clone());
+    M.set_dia_ptr(UPTR);
+    M.set_levels();
+
+    M
+}
+
+pub fn SSOR_symmetric(A: &SymmetricMatrix, omega: f64) -> Matrix {
+    // SSOR preconditioner of symmetric storage, the upper elements of a row are
+    // taken from a temporary strict upper triangle, the full matrix is never built
+    let L = A.lower();
+    let U = &A.upper();
+    check(L);
+    check_omega(omega);
+    let m = L.num_rows();
+    let dia = L.dia_ptr();
+    let mut AA = Vec::with_capacity(L.AA().len() + U.AA().len());
+    let mut JA = Vec::with_capacity(L.JA().len() + U.JA().len());
+    let mut IA = vec![0usize; m+1];
+    let mut UPTR = vec![usize::MAX; m];
+
+    for i in 0..m {
+        if L.AA()[dia[i]] == 0f64 {
+            panic!("diagonal element error");
+        }
+
+        // for lower elements
+        for j in L.IA()[i]..dia[i] {
+            AA.push(omega * L.AA()[j] / L.AA()[dia[L.JA()[j]]]);
+            JA.push(L.JA()[j]);
+        }
+
+        // for diagonal elements
+        UPTR[i] = AA.len();
+        AA.push((2f64 - omega) / L.AA()[dia[i]]);
+        JA.push(i);
+
+        // for upper elements
+        for j in U.IA()[i]..U.IA()[i+1] {
+            AA.push(omega * U.AA()[j] / (2f64 - omega));
+            JA.push(U.JA()[j]);
+        }
+
+        IA[i+1] = AA.len();
+    }
+
+    let mut M = Matrix::from(AA, JA, IA);
+    M.set_dia_ptr(UPTR);
+    M.set_levels();
 
     M
 }
 
 //-----------------------------------------------------------------------------------------------------------//
-pub fn SOR(A: &Matrix) -> Matrix {
-    let m =  A.num_rows();
+pub fn optimal_omega(A: &Matrix) -> f64 {
+    // estimate of the optimal relaxation parameter, omega = 2 / (1 + sqrt(1 - rho^2))
+    // rho is the spectral radius of the Jacobi iteration matrix I - D^-1 A, by power iteration.
+    // the formula is exact for consistently ordered SPD matrices and a heuristic otherwise
+    check(A);
+    let m = A.num_rows();
+    let dia = A.dia_ptr();
+
+    let jacobi = |x: &Vector| -> Vector {
+        let Ax = A * x;
+        let mut y = Vector::from(vec![0f64; m]);
+
+        y.par_iter_mut().enumerate()
+            .for_each(|(i, y)| {
+                *y = x[i] - Ax[i] / A.AA()[dia[i]];
+            });
+
+        y
+    };
+
+    // the dominant eigenvalues of G come in pairs +- rho, so two steps are taken at once
+    let mut x = Vector::from(vec![1f64; m]);
+    let mut rho = 0f64;
 
+    for _ in 0..100 {
+        let norm = x.l2_norm();
+        if norm == 0f64 {
+            break;
+        }
+
+        x.par_iter_mut().for_each(|x| *x /= norm);
+        x = jacobi(&jacobi(&x));
+
+        let estimate = x.l2_norm().sqrt();
+        let change = (estimate - rho).abs();
+        rho = estimate;
+
+        if change < 1.0E-6 * rho {
+            break;
+        }
+    }
 
-    Matrix::new()
+    if rho >= 1f64 {
+        // Jacobi diverges, no relaxation is recommended
+        return 1f64;
+    }
+
+    2f64 / (1f64 + (1f64 - rho * rho).sqrt())
 }
 
 //-----------------------------------------------------------------------------------------------------------//
+// levels with fewer rows are solved without spawning parallel tasks
+const MIN_LEVEL: usize = 256;
+
 pub fn LU_solve(M: &Matrix, v: &Vector) -> Vector {
     // LU solver
     // L: unit lower matrix
     // U: upper matrix
+    // with cached level sets, the rows of each level are solved in parallel
 
     let m = M.num_rows();
     let UPTR = match M.UPTR() {
@@ -381,6 +911,67 @@ pub fn LU_solve(M: &Matrix, v: &Vector) -> Vector {
             panic!("can not find diagonal pointer");
         }
     };
+
+    if let Some((forward, backward)) = M.levels() {
+        let (AA, JA, IA) = (M.AA(), M.JA(), M.IA());
+
+        // foward sweep, z is stored in the order of the forward levels
+        let lower = |k: usize, done: &[f64]| {
+            let i = forward.rows()[k];
+            let mut value = v[i];
+
+            for j in IA[i]..UPTR[i] {
+                value -= AA[j] * done[forward.position()[JA[j]]];
+            }
+
+            value
+        };
+
+        let mut z = vec![0f64; m];
+        for l in 0..forward.num_levels() {
+            let (start, end) = (forward.ptr()[l], forward.ptr()[l+1]);
+            let (done, level) = z.split_at_mut(start);
+            let level = &mut level[..end-start];
+
+            if level.len() < MIN_LEVEL {
+                level.iter_mut().enumerate().for_each(|(k, zk)| *zk = lower(start + k, done));
+            } else {
+                level.par_iter_mut().enumerate().for_each(|(k, zk)| *zk = lower(start + k, done));
+            }
+        }
+
+        // backward sweep, x is stored in the order of the backward levels
+        let upper = |k: usize, done: &[f64]| {
+            let i = backward.rows()[k];
+            let mut value = z[forward.position()[i]];
+
+            for j in UPTR[i]+1..IA[i+1] {
+                value -= AA[j] * done[backward.position()[JA[j]]];
+            }
+
+            value * AA[UPTR[i]]
+        };
+
+        let mut x = vec![0f64; m];
+        for l in 0..backward.num_levels() {
+            let (start, end) = (backward.ptr()[l], backward.ptr()[l+1]);
+            let (done, level) = x.split_at_mut(start);
+            let level = &mut level[..end-start];
+
+            if level.len() < MIN_LEVEL {
+                level.iter_mut().enumerate().for_each(|(k, xk)| *xk = upper(start + k, done));
+            } else {
+                level.par_iter_mut().enumerate().for_each(|(k, xk)| *xk = upper(start + k, done));
+            }
+        }
+
+        let x = (0..m)
+            .map(|i| x[backward.position()[i]])
+            .collect::<Vec<f64>>();
+
+        return Vector::from(x);
+    }
+
     let mut x = Vector::from(vec![0.0; m]);
 
     // foward sweep
@@ -400,4 +991,173 @@ pub fn LU_solve(M: &Matrix, v: &Vector) -> Vector {
     }
 
     x
-}
\ No newline at end of file
+}
+
+/***********************************************************************************************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linear_algebra::{msolver, testing::{convection_diffusion, laplacian}};
+
+    fn relative_error(A: &Matrix, M: &Matrix, r: &Vector) -> f64 {
+        // |r - A M^-1 r| / |r|
+        (r - &(A * &M.apply(r))).l2_norm() / r.l2_norm()
+    }
+
+    fn lu_product(M: &Matrix) -> Vec<Vec<f64>> {
+        // dense L U of a factor in the LU layout, L is unit lower
+        let m = M.num_rows();
+        let UPTR = M.UPTR().unwrap();
+        let mut L = vec![vec![0f64; m]; m];
+        let mut U = vec![vec![0f64; m]; m];
+
+        for i in 0..m {
+            L[i][i] = 1f64;
+            U[i][i] = 1f64 / M.AA()[UPTR[i]];
+
+            for k in M.IA()[i]..UPTR[i] {
+                L[i][M.JA()[k]] = M.AA()[k];
+            }
+            for k in UPTR[i]+1..M.IA()[i+1] {
+                U[i][M.JA()[k]] = M.AA()[k];
+            }
+        }
+
+        (0..m).map(|i| (0..m).map(|j| (0..m).map(|k| L[i][k] * U[k][j]).sum()).collect()).collect()
+    }
+
+    #[test]
+    fn ilu_pivots_on_the_diagonal_of_a() {
+        let A = convection_diffusion(6, 5.0);
+        let M = ILU(&A);
+        let (dia, uptr) = (A.dia_ptr(), M.UPTR().unwrap());
+
+        for i in 0..A.num_rows() {
+            assert_eq!(M.AA()[uptr[i]], 1f64 / A.AA()[dia[i]]);
+        }
+    }
+
+    #[test]
+    fn iluk_reproduces_a_on_its_pattern() {
+        let A = convection_diffusion(6, 5.0);
+        let LU = lu_product(&ILUK(&A, 0));
+
+        for (i, row) in LU.iter().enumerate() {
+            for k in A.IA()[i]..A.IA()[i+1] {
+                let j = A.JA()[k];
+                assert!((row[j] - A.AA()[k]).abs() < 1.0E-12, "({i}, {j})");
+            }
+        }
+    }
+
+    #[test]
+    fn iluk_with_enough_levels_is_exact() {
+        let A = convection_diffusion(8, 5.0);
+        let r = A.row_sums();
+
+        assert!(relative_error(&A, &ILUK(&A, A.num_rows()), &r) < 1.0E-12);
+        assert!(relative_error(&A, &ILUK(&A, 1), &r) < relative_error(&A, &ILUK(&A, 0), &r));
+    }
+
+    fn same_factor(M: &Matrix, N: &Matrix) {
+        // same pattern and values up to rounding, a * (1 / d) against a / d
+        assert_eq!((M.JA(), M.IA(), M.UPTR()), (N.JA(), N.IA(), N.UPTR()));
+
+        for (a, b) in M.AA().iter().zip(N.AA()) {
+            assert!((a - b).abs() <= 4.0 * f64::EPSILON * b.abs(), "{a} and {b}");
+        }
+    }
+
+    #[test]
+    fn sor_and_ssor_with_omega_one_are_gs_and_sgs() {
+        let A = convection_diffusion(8, 3.0);
+
+        same_factor(&SOR(&A, 1.0), &GS(&A));
+        same_factor(&SSOR(&A, 1.0), &SGS(&A));
+    }
+
+    #[test]
+    fn jacobi_factor_divides_by_the_diagonal() {
+        let A = convection_diffusion(8, 3.0);
+        let r = A.row_sums();
+
+        assert_eq!(Jacobi_factor(&A).apply(&r), Jacobi(&A, &r));
+    }
+
+    #[test]
+    fn optimal_omega_of_the_poisson_matrix() {
+        // the Jacobi spectral radius is cos(pi h), omega = 2 / (1 + sin(pi h)) with h = 1 / (n + 1)
+        for n in [10, 20] {
+            let omega = optimal_omega(&laplacian(n, n));
+            let exact = 2.0 / (1.0 + (std::f64::consts::PI / (n + 1) as f64).sin());
+
+            assert!((1.0..2.0).contains(&omega));
+            assert!((omega - exact).abs() < 1.0E-3, "omega {omega} and {exact}");
+        }
+    }
+
+    #[test]
+    fn level_scheduled_solve_matches_the_serial_sweep() {
+        // the levels of a grid in natural order are its anti-diagonals, up to 300 rows
+        let A = laplacian(300, 300);
+        let r = Vector::from((0..A.num_rows()).map(|i| (i % 11) as f64 - 5.0).collect::<Vec<f64>>());
+
+        for M in [ILU(&A), SSOR(&A, 1.2)] {
+            let (forward, backward) = M.levels().unwrap();
+            for levels in [forward, backward] {
+                let size = |l: usize| levels.ptr()[l+1] - levels.ptr()[l];
+                assert!((0..levels.num_levels()).any(|l| size(l) >= MIN_LEVEL));
+                assert!((0..levels.num_levels()).any(|l| size(l) < MIN_LEVEL));
+            }
+
+            let mut serial = M.clone();
+            serial.set_dia_ptr(M.UPTR().unwrap().clone());
+            assert!(serial.levels().is_none());
+
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+            assert_eq!(pool.install(|| LU_solve(&M, &r)), LU_solve(&serial, &r));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "non-positive pivot error")]
+    fn ic_rejects_a_negative_diagonal() {
+        let A = -1f64 * &laplacian(4, 4);
+        IC(&A);
+    }
+
+    #[test]
+    fn ilut_without_dropping_is_exact() {
+        let A = convection_diffusion(12, 5.0);
+        let r = A.row_sums();
+        let M = ILUT(&A, usize::MAX, 0.0);
+
+        assert!(relative_error(&A, &M, &r) < 1.0E-12);
+    }
+
+    #[test]
+    fn ilut_keeps_at_most_fill_entries_per_part() {
+        let A = convection_diffusion(16, 5.0);
+        let fill = 3;
+        let M = ILUT(&A, fill, 1.0E-4);
+        let UPTR = M.UPTR().unwrap();
+
+        for (i, &k) in UPTR.iter().enumerate() {
+            assert!(k - M.IA()[i] <= fill);
+            assert!(M.IA()[i+1] - k - 1 <= fill);
+            assert_eq!(M.JA()[k], i);
+        }
+    }
+
+    #[test]
+    fn ilut_reduces_gmres_iterations() {
+        let A = convection_diffusion(32, 10.0);
+        let b = A.row_sums();
+
+        let none = msolver::GMRES(1000, 1.0E-8, 10, &A, &b, Preconditioner::None, None);
+        let ilut = msolver::GMRES(1000, 1.0E-8, 10, &A, &b, Preconditioner::ILUT { fill: 10, drop_tol: 1.0E-3 }, None);
+
+        assert!(none.converged && ilut.converged);
+        assert!(4 * ilut.iterations < none.iterations, "{} -> {}", none.iterations, ilut.iterations);
+    }
+}
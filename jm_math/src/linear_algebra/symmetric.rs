@@ -0,0 +1,192 @@
+use std::{
+    ops::Mul,
+    path::PathBuf,
+};
+use rayon::prelude::*;
+use crate::linear_algebra::{
+    vector::Vector,
+    matrix::Matrix,
+    mtx::{self, MtxError},
+};
+
+#[derive(Debug, Clone)]
+pub struct SymmetricMatrix {
+    lower: Matrix
+}
+
+/***********************************************************************************************************/
+impl SymmetricMatrix {
+    pub fn from_lower(A: &Matrix) -> Self {
+        // keep the lower triangle and the diagonal of A, the upper part is not referenced
+        assert!(A.num_rows() == A.num_cols());
+
+        let rows = (0..A.num_rows()).into_par_iter()
+            .map(|i| {
+                (A.IA()[i]..A.IA()[i+1])
+                    .filter(|&j| A.JA()[j] <= i)
+                    .map(|j| (A.JA()[j], A.AA()[j]))
+                    .collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+
+        Self::with_lower(Matrix::from_rows(A.num_cols(), rows))
+    }
+
+    pub(crate) fn with_lower(lower: Matrix) -> Self {
+        Self {
+            lower
+        }
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn import_mtx(path: &str) -> Result<SymmetricMatrix, MtxError> {
+        // import a symmetric or general Matrix Market file without expanding it
+        // a general file is an error unless a(i,j) = a(j,i) for every entry
+        mtx::read_symmetric(&PathBuf::from(path))
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn num_rows(&self) -> usize {
+        self.lower.num_rows()
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.lower.num_cols()
+    }
+
+    pub fn lower(&self) -> &Matrix {
+        // lower triangle with the diagonal, IC and GS only reference this part
+        &self.lower
+    }
+
+    pub(crate) fn upper(&self) -> Matrix {
+        // strict upper triangle, the transpose of the strict lower triangle
+        // built on each call for a temporary use, it is not kept
+        let L = &self.lower;
+        let rows = (0..L.num_rows()).into_par_iter()
+            .map(|i| {
+                (L.IA()[i]..L.IA()[i+1])
+                    .filter(|&j| L.JA()[j] < i)
+                    .map(|j| (L.JA()[j], L.AA()[j]))
+                    .collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+
+        Matrix::from_rows(L.num_cols(), rows).transpose()
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn to_full(&self) -> Matrix {
+        // expand to general storage, L + L^T - D
+        let L = &self.lower;
+        let U = &self.upper();
+
+        let rows = (0..L.num_rows()).into_par_iter()
+            .map(|i| {
+                let lower = (L.IA()[i]..L.IA()[i+1]).map(|j| (L.JA()[j], L.AA()[j]));
+                let upper = (U.IA()[i]..U.IA()[i+1]).map(|j| (U.JA()[j], U.AA()[j]));
+
+                lower.chain(upper).collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+
+        Matrix::from_rows(L.num_cols(), rows)
+    }
+}
+
+/***********************************************************************************************************/
+impl Mul<&Vector> for &SymmetricMatrix {
+    type Output = Vector;
+
+    fn mul(self, rhs: &Vector) -> Self::Output {
+        // y = L x + L^T x - D x from the lower triangle alone. the rows are split in blocks,
+        // each block scatters l(i,j) x(i) into y(j) of a local buffer over the columns [lo, end)
+        // it references, and the buffers are summed afterwards
+        let L = &self.lower;
+        let m = L.num_rows();
+        assert!(m == rhs.num_rows());
+
+        let size = usize::max(1024, m.div_ceil(rayon::current_num_threads()));
+        let blocks = (0..m.div_ceil(size)).into_par_iter()
+            .map(|block| {
+                let start = block * size;
+                let end = usize::min(start + size, m);
+                let lo = (start..end)
+                    .filter(|&i| L.IA()[i] < L.IA()[i+1])
+                    .map(|i| L.JA()[L.IA()[i]])
+                    .fold(start, usize::min);
+                let mut y = vec![0f64; end - lo];
+
+                for i in start..end {
+                    let mut sum = 0f64;
+
+                    for k in L.IA()[i]..L.IA()[i+1] {
+                        let j = L.JA()[k];
+                        sum += L.AA()[k] * rhs[j];
+
+                        if j < i {
+                            y[j - lo] += L.AA()[k] * rhs[i];
+                        }
+                    }
+
+                    y[i - lo] += sum;
+                }
+
+                (lo, end, y)
+            }).collect::<Vec<_>>();
+
+        let AA = (0..m).into_par_iter()
+            .with_min_len(1024)
+            .map(|i| {
+                blocks.iter()
+                    .filter(|(lo, end, _)| *lo <= i && i < *end)
+                    .map(|(lo, _, y)| y[i - lo])
+                    .sum::<f64>()
+            }).collect::<Vec<f64>>();
+
+        Vector::from(AA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linear_algebra::{
+        msolver,
+        preconditioner::Preconditioner,
+        testing::{laplacian, scrambled_laplacians},
+    };
+
+    #[test]
+    fn product_matches_full_storage() {
+        // four threads give several row blocks, the scrambled ordering spreads the scatter
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+
+        for A in [laplacian(90, 80), scrambled_laplacians(60, 40, 3)] {
+            let S = SymmetricMatrix::from_lower(&A);
+            let x = Vector::from((0..A.num_rows()).map(|i| (i % 7) as f64 - 3.0).collect::<Vec<f64>>());
+            let y = &A * &x;
+
+            assert!((&pool.install(|| &S * &x) - &y).l2_norm() < 1.0E-12 * y.l2_norm());
+            assert!((&(&S * &x) - &y).l2_norm() < 1.0E-12 * y.l2_norm());
+            assert_eq!(S.to_full().AA(), A.AA());
+        }
+    }
+
+    #[test]
+    fn every_preconditioner_is_available() {
+        let A = laplacian(20, 20);
+        let S = SymmetricMatrix::from_lower(&A);
+        let r = A.row_sums();
+
+        for preconditioner in [
+            Preconditioner::Jacobi, Preconditioner::GS, Preconditioner::SGS,
+            Preconditioner::SOR(1.2), Preconditioner::SSOR(1.2), Preconditioner::ILU,
+            Preconditioner::ILUK(1), Preconditioner::ILUT { fill: 5, drop_tol: 1.0E-3 }, Preconditioner::IC
+        ] {
+            let z = preconditioner.from_symmetric(&S).unwrap().apply(&r);
+            let w = preconditioner.from(&A).unwrap().apply(&r);
+            assert!((&z - &w).l2_norm() < 1.0E-12 * w.l2_norm(), "{preconditioner:?}");
+        }
+
+        let result = msolver::PCG(100, 1.0E-10, &S, &r, Preconditioner::SGS, None);
+        assert!(result.converged);
+    }
+}
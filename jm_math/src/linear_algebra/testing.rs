@@ -0,0 +1,46 @@
+// matrices shared by the unit tests of linear_algebra
+use crate::linear_algebra::{matrix::Matrix, triplet::TripletBuilder};
+
+fn stencil(nx: usize, ny: usize, copies: usize, c: f64, id: impl Fn(usize) -> usize) -> Matrix {
+    // disconnected copies of the upwind 5-point convection-diffusion stencil on an nx x ny grid
+    // vertex v = (copy * ny + j) * nx + i is numbered id(v), c = 0 is the Laplacian
+    let m = nx * ny * copies;
+    let mut builder = TripletBuilder::new(m, m);
+
+    for v in 0..m {
+        let (i, j) = (v % nx, v / nx % ny);
+        builder.push(id(v), id(v), 4.0 + 2.0 * c);
+        if i > 0 {
+            builder.push(id(v), id(v - 1), -1.0 - c);
+        }
+        if i + 1 < nx {
+            builder.push(id(v), id(v + 1), -1.0);
+        }
+        if j > 0 {
+            builder.push(id(v), id(v - nx), -1.0 - c);
+        }
+        if j + 1 < ny {
+            builder.push(id(v), id(v + nx), -1.0);
+        }
+    }
+
+    builder.build()
+}
+
+pub(crate) fn laplacian(nx: usize, ny: usize) -> Matrix {
+    // 5-point Laplacian, symmetric positive definite
+    stencil(nx, ny, 1, 0.0, |v| v)
+}
+
+pub(crate) fn convection_diffusion(n: usize, c: f64) -> Matrix {
+    // upwind convection-diffusion on an n x n grid, nonsymmetric for c > 0
+    stencil(n, n, 1, c, |v| v)
+}
+
+pub(crate) fn scrambled_laplacians(nx: usize, ny: usize, copies: usize) -> Matrix {
+    // disconnected copies of the 5-point Laplacian, scrambled by a stride of 37
+    let m = nx * ny * copies;
+    assert!(!m.is_multiple_of(37));
+
+    stencil(nx, ny, copies, 0.0, |v| v * 37 % m)
+}
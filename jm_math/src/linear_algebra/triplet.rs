@@ -0,0 +1,123 @@
+use rayon::prelude::*;
+use crate::linear_algebra::matrix::Matrix;
+
+#[derive(Debug, Clone)]
+pub struct TripletBuilder {
+    m: usize,
+    n: usize,
+    entries: Vec<(usize, usize, f64)>
+}
+
+/***********************************************************************************************************/
+impl TripletBuilder {
+    pub fn new(m: usize, n: usize) -> Self {
+        Self {
+            m,
+            n,
+            entries: Vec::new()
+        }
+    }
+
+    pub fn with_capacity(m: usize, n: usize, capacity: usize) -> Self {
+        Self {
+            m,
+            n,
+            entries: Vec::with_capacity(capacity)
+        }
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn num_rows(&self) -> usize {
+        self.m
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.n
+    }
+
+    pub fn len(&self) -> usize {
+        // number of pushed contributions, duplicates included
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn push(&mut self, i: usize, j: usize, v: f64) {
+        // add a contribution v to the entry (i, j), in any order
+        if i >= self.m || j >= self.n {
+            panic!("entry ({i}, {j}) is out of range for a [{} x {}] matrix", self.m, self.n);
+        }
+
+        self.entries.push((i, j, v));
+    }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn build(self) -> Matrix {
+        // convert to CSR, duplicated entries are summed in the order they were pushed
+        let m = self.m;
+        let mut entries = self.entries;
+
+        // stable sort keeps the summation order of duplicates deterministic
+        entries.par_sort_by_key(|&(i, j, _)| (i, j));
+
+        let rows = (0..m).into_par_iter()
+            .map(|i| {
+                let k1 = entries.partition_point(|e| e.0 < i);
+                let k2 = entries.partition_point(|e| e.0 <= i);
+                let mut row: Vec<(usize, f64)> = Vec::with_capacity(k2 - k1);
+
+                for &(_, j, v) in &entries[k1..k2] {
+                    match row.last_mut() {
+                        Some(last) if last.0 == j => last.1 += v,
+                        _ => row.push((j, v)),
+                    }
+                }
+
+                row
+            }).collect::<Vec<Vec<_>>>();
+
+        Matrix::from_rows(self.n, rows)
+    }
+}
+
+/***********************************************************************************************************/
+impl Extend<(usize, usize, f64)> for TripletBuilder {
+    fn extend<T: IntoIterator<Item = (usize, usize, f64)>>(&mut self, iter: T) {
+        for (i, j, v) in iter {
+            self.push(i, j, v);
+        }
+    }
+}
+
+/***********************************************************************************************************/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates_are_summed() {
+        let mut builder = TripletBuilder::new(3, 4);
+        builder.push(2, 1, 1.0);
+        builder.push(0, 3, 2.0);
+        builder.push(2, 1, 0.5);
+        builder.extend([(0, 0, 4.0), (0, 3, -2.0), (2, 0, 3.0)]);
+
+        assert_eq!(builder.len(), 6);
+
+        let A = builder.build();
+
+        assert_eq!((A.num_rows(), A.num_cols()), (3, 4));
+        assert_eq!(A.IA(), &vec![0, 2, 2, 4]);
+        assert_eq!(A.JA(), &vec![0, 3, 0, 1]);
+        assert_eq!(A.AA(), &vec![4.0, 0.0, 3.0, 1.5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn push_out_of_range() {
+        TripletBuilder::new(2, 2).push(0, 2, 1.0);
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     fmt,
-    convert::From
+    convert::From,
+    path::PathBuf,
 };
 use std::ops::{
     Add, 
@@ -14,6 +15,7 @@ use std::ops::{
     DerefMut,
 };
 use rayon::prelude::*;
+use crate::linear_algebra::mtx::{self, MtxError};
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Vector {
@@ -51,6 +53,17 @@ impl Vector {
 
         Vector::from(AA)
     }
+
+//-----------------------------------------------------------------------------------------------------------//
+    pub fn import_mtx(path: &str) -> Result<Vector, MtxError> {
+        // import a vector from a Matrix Market file
+        mtx::read_vector(&PathBuf::from(path))
+    }
+
+    pub fn export_mtx(&self, path: &str) -> Result<(), MtxError> {
+        // export a vector to a Matrix Market file in array format
+        mtx::write_vector(self, &PathBuf::from(path))
+    }
 }
 
 /***********************************************************************************************************/
@@ -113,7 +113,7 @@ pub fn test3() {
     //* multi thread operation */
     let bench_result = run_benchmark(n, |_| {
         let v = v.lock().unwrap();
-        let v = &M * &v;
+        let v = &M * &*v;
 
         v.par_iter().sum::<f64>();
     });
@@ -146,13 +146,17 @@ pub fn test4() {
     // println!("{:.2}", A);
     // println!("{:.2}", b);
     let bench_result = run_benchmark(10, |_| {
-        let x = msolver::GMRES(1000, 1.0E-13, 5, &A, &b, Preconditioner::GS);
+        let result = msolver::GMRES(1000, 1.0E-13, 5, &A, &b, Preconditioner::GS, None);
+        println!("{result}");
+        let x = result.solution;
         println!("{:.2}", x.AA().par_iter().sum::<f64>());
     });
     let time0 = bench_result.get_average() as f64 * 1.0E-9;
 
     let bench_result = run_benchmark(10, |_| {
-        let x = msolver::CG(1000, 1.0E-13, &A, &b);
+        let result = msolver::CG(1000, 1.0E-13, &A, &b, None);
+        println!("{result}");
+        let x = result.solution;
         println!("{:.2}", x.AA().par_iter().sum::<f64>());
     });
     let time1 = bench_result.get_average() as f64 * 1.0E-9;
@@ -174,25 +178,33 @@ pub fn test5() {
     // println!("{}", x.par_iter().sum::<f64>());
 
     let bench_result = run_benchmark(1, |_| {
-        let x = msolver::CG(1000, tol, &A, &b);
+        let result = msolver::CG(1000, tol, &A, &b, None);
+        println!("{result}");
+        let x = result.solution;
         println!("{:.6}", x.par_iter().sum::<f64>());
     });
     let time1 = bench_result.get_average() as f64 * 1.0E-9;
 
     let bench_result = run_benchmark(1, |_| {
-        let x = msolver::Gauss_Seidel(1000, tol, &A, &b);
+        let result = msolver::Gauss_Seidel(1000, tol, &A, &b, None);
+        println!("{result}");
+        let x = result.solution;
         println!("{:.6}", x.par_iter().sum::<f64>());
     });
     let time2 = bench_result.get_average() as f64 * 1.0E-9;
 
     let bench_result = run_benchmark(1, |_| {
-        let x = msolver::GMRES(1000, tol, 5, &A, &b, Preconditioner::GS);
+        let result = msolver::GMRES(1000, tol, 5, &A, &b, Preconditioner::GS, None);
+        println!("{result}");
+        let x = result.solution;
         println!("{:.6}", x.par_iter().sum::<f64>());
     });
     let time0 = bench_result.get_average() as f64 * 1.0E-9;
 
     let bench_result = run_benchmark(1, |_| {
-        let x = msolver::HGMRES(1000, tol, 5, &A, &b, Preconditioner::GS);
+        let result = msolver::HGMRES(1000, tol, 5, &A, &b, Preconditioner::GS, None);
+        println!("{result}");
+        let x = result.solution;
         println!("{:.6}", x.par_iter().sum::<f64>());
     });
     let time3 = bench_result.get_average() as f64 * 1.0E-9;
@@ -224,7 +236,9 @@ pub fn test6() {
     let b = Vector::from(vec![8, 7, 15]);
 
     let A = Matrix::from(AA, JA, IA);
-    let x = msolver::HGMRES(1000, 1.0E-13, 3, &A, &b, Preconditioner::GS);
+    let result = msolver::HGMRES(1000, 1.0E-13, 3, &A, &b, Preconditioner::GS, None);
+    println!("{result}");
+    let x = result.solution;
     println!("{:.2}", x);
 }
 
@@ -241,8 +255,7 @@ pub fn test7() {
     let IA = vec![0usize, 1, 3, 5, 7, 9, 12, 15, 18, 20, 23, 26, 29];
     let M = Matrix::from(AA, JA, IA);
 
-    let v = (0..M.num_rows()).map(|i| M.AA()[M.IA()[i]..M.IA()[i+1]].iter().sum()).collect::<Vec<f64>>();
-    let v = Vector::from(v);
+    let v = M.row_sums();
 
     // let x = preconditioner::level_schduling(&M, &v);
     // println!("{:.2}", x);
@@ -278,7 +291,8 @@ pub fn test7() {
     // println!("CG: {:>10.4} sec", time2);
 
 
-    let M = Matrix::import_mtx("res/bcsstk14.mtx");
+    let M = Matrix::import_mtx("res/bcsstk14.mtx")
+        .expect("can not import matrix");
     // let M = Matrix::import_mtx("res/nos4.mtx");
     // let M = Matrix::import_mtx("res/jpwh_991.mtx");
     // let M = Matrix::import_mtx("res/bcsstm12.mtx");
@@ -287,24 +301,42 @@ pub fn test7() {
     let v = Vector::from(vec![1f64; m]);
     let v = &M * &v;
 
-    let x = msolver::CG(1000, 1.0E-6, &M, &v);
+    let result = msolver::CG(1000, 1.0E-6, &M, &v, None);
+    println!("{result}");
+    let x = result.solution;
+    println!("{:.4}\n", x.iter().sum::<f64>());
+    let result = msolver::PCG(1000, 1.0E-6, &M, &v, Preconditioner::IC, None);
+    println!("{result}");
+    let x = result.solution;
+    println!("{:.4}\n", x.iter().sum::<f64>());
+    let result = msolver::GMRES(1000, 1.0E-6, 10, &M, &v, Preconditioner::SGS, None);
+    println!("{result}");
+    let x = result.solution;
     println!("{:.4}\n", x.iter().sum::<f64>());
-    let x = msolver::GMRES(1000, 1.0E-6, 10, &M, &v, Preconditioner::SGS);
+    let result = msolver::GMRES(1000, 1.0E-6, 10, &M, &v, Preconditioner::ILU, None);
+    println!("{result}");
+    let x = result.solution;
     println!("{:.4}\n", x.iter().sum::<f64>());
-    let x = msolver::GMRES(1000, 1.0E-6, 10, &M, &v, Preconditioner::ILU);
+    let result = msolver::HGMRES(1000, 1.0E-6, 10, &M, &v, Preconditioner::SGS, None);
+    println!("{result}");
+    let x = result.solution;
     println!("{:.4}\n", x.iter().sum::<f64>());
-    let x = msolver::HGMRES(1000, 1.0E-6, 10, &M, &v, Preconditioner::SGS);
+    let result = msolver::BiCGSTAB(1000, 1.0E-6, &M, &v, Preconditioner::ILU, None);
+    println!("{result}");
+    let x = result.solution;
     println!("{:.4}", x.iter().sum::<f64>());
 }
 
 //-----------------------------------------------------------------------------------------------------------//
 #[allow(non_snake_case)]
 pub fn test8() {
-    let AA = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-    let JA = vec![0usize, 3, 1, 2, 3, 0, 2, 3, 3];
-    let IA = vec![0usize, 2, 5, 8, 9];
     // let M = Matrix::import_mtx("./res/bcsstk01.mtx");
-    let A = Matrix::from(AA, JA, IA);
+    let A = Matrix::from_dense(vec![
+        vec![1, 0, 0, 2],
+        vec![0, 3, 4, 5],
+        vec![6, 0, 7, 8],
+        vec![0, 0, 0, 9],
+    ], false);
     let v = Vector::from(vec![1, 3, 13, 9]);
 
     println!("{:.2}", A);
@@ -378,9 +410,9 @@ pub fn test9() {
     
     // let M = Matrix::import_mtx(r"res/mtx/bcsstk14.mtx");
     // let M = Matrix::import_mtx(r"res/mtx/bcsstk01.mtx");
-    let M = Matrix::import_mtx(r"res/mtx/orsirr_1.mtx");
-    let v = (0..M.num_rows()).map(|i| M.AA()[M.IA()[i]..M.IA()[i+1]].iter().sum()).collect::<Vec<f64>>();
-    let v = Vector::from(v);
+    let M = Matrix::import_mtx(r"res/mtx/orsirr_1.mtx")
+        .expect("can not import matrix");
+    let v = M.row_sums();
     let perm = M.RCM();
     
     let M2 = M.clone();
@@ -389,13 +421,17 @@ pub fn test9() {
     let v1 = v.permutate(&perm);
 
     let bench_result = run_benchmark(10, |_| {
-        let x = msolver::GMRES(1000, 1.0E-7, 10, &M2, &v2, Preconditioner::ILU);
+        let result = msolver::GMRES(1000, 1.0E-7, 10, &M2, &v2, Preconditioner::ILU, None);
+        println!("{result}");
+        let x = result.solution;
         println!("{:.6}", x.par_iter().sum::<f64>());
     });
     let time1 = bench_result.get_average() as f64 * 1.0E-9;
 
     let bench_result = run_benchmark(10, |_| {
-        let x = msolver::GMRES(1000, 1.0E-7, 10, &M1, &v1, Preconditioner::ILU);
+        let result = msolver::GMRES(1000, 1.0E-7, 10, &M1, &v1, Preconditioner::ILU, None);
+        println!("{result}");
+        let x = result.solution;
         println!("{:.6}", x.par_iter().sum::<f64>());
     });
     let time0 = bench_result.get_average() as f64 * 1.0E-9;
@@ -411,29 +447,22 @@ pub fn test9() {
 #[allow(non_snake_case)]
 fn tri_diagonal(m: usize) -> (Matrix, Vector) {
     let a = 2.0;
-    let mut AA = vec![a, 1.0];
-    let mut JA = vec![0usize, 1];
-    let mut IA = vec![0];
+    let mut builder = TripletBuilder::with_capacity(m, m, 3 * m);
     let mut v = vec![a + 2.0; m - 2];
 
-    for i in 1..m - 1 {
-        IA.push(AA.len());
+    for i in 0..m {
+        builder.push(i, i, a);
 
-        AA.push(1.0);
-        AA.push(a);
-        AA.push(1.0);
-        
-        JA.push(i - 1);
-        JA.push(i);
-        JA.push(i + 1);
-    }
+        if i > 0 {
+            builder.push(i, i - 1, 1.0);
+        }
 
-    IA.push(AA.len());
-    AA.append(&mut vec![1.0, a]);
-    JA.append(&mut vec![m - 2, m - 1]);
-    IA.push(AA.len());
+        if i < m - 1 {
+            builder.push(i, i + 1, 1.0);
+        }
+    }
 
-    let M = Matrix::from(AA, JA, IA);
+    let M = builder.build();
 
     v.insert(0, 1.0 + a);
     v.push(1.0 + a);